@@ -1,29 +1,281 @@
-use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    get,
+    http::{header, StatusCode as HttpStatus},
+    middleware::{from_fn, Next},
+    post, web, App, HttpRequest, HttpResponse, HttpServer, Responder, ResponseError,
+};
+use base64::prelude::*;
+use indexmap::IndexMap;
 use once_cell::sync::Lazy;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use redis::{
+    aio::{ConnectionManager, ConnectionManagerConfig},
+    FromRedisValue,
+};
+use rusqlite::{params, Connection};
+use rust_server_monitor::{bind_addr, escape_label, LockExt};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{Schema, SchemaObject, SubschemaValidation},
+    schema_for, JsonSchema,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    tls::TlsInfo,
+    redirect, Client, ClientBuilder, Method, NoProxy, Proxy, StatusCode, Url,
+};
+use subtle::ConstantTimeEq;
+use serde::{de::{self, DeserializeOwned}, Deserialize, Deserializer, Serialize, Serializer};
+use x509_parser::parse_x509_certificate;
 use std::{
-    collections::HashMap,
-    env,
-    fs::File,
-    io::{Read, Write},
-    sync::RwLock,
-    time::Duration,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    env, fmt,
+    fs::{self, File},
+    io::{self, BufReader, Read, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+    mem,
+    net::{IpAddr, Ipv4Addr},
+    sync::{
+        atomic::{AtomicBool, AtomicU16, Ordering},
+        Arc, Mutex, MutexGuard, RwLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use tokio::time;
-use futures::stream::{self, StreamExt};
-use chrono::{Utc, FixedOffset};
+use tokio::{
+    net::TcpStream,
+    sync::{broadcast, watch, OnceCell},
+    time,
+};
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::EnvFilter;
+use futures::{
+    future,
+    stream::{self, StreamExt},
+};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
 use dotenv::dotenv;
-use serde_json;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
 
 const FRONTENDS_FILE: &str = "frontends.json";
+const HISTORY_FILE: &str = "website_history.json";
+const INCIDENTS_FILE: &str = "incidents.json";
+const INCIDENT_ARCHIVE_PREFIX: &str = "incidents-";
+const USAGE_CACHE_FILE: &str = "usage_cache.json";
+const REDIS_FRONTENDS_KEY: &str = "rust-server-monitor:frontends";
+const REDIS_USAGE_KEY: &str = "rust-server-monitor:usage";
+const REDIS_ALERT_STATE_KEY: &str = "rust-server-monitor:alert_state";
+const REDIS_ALERT_PAUSE_KEY: &str = "rust-server-monitor:alert_pause";
+const REDIS_POLLER_KEY: &str = "rust-server-monitor:poller";
+// How long the poller lock outlives a backend that stopped renewing it. It is
+// renewed three times per TTL, independently of how long a poll batch takes.
+const POLLER_LOCK_TTL: Duration = Duration::from_secs(10);
+const REDIS_TIMEOUT: Duration = Duration::from_secs(2);
+// Rows returned per table by /api/history.
+const MAX_HISTORY_ROWS: u32 = 10_000;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 struct FrontendInfo {
     name: String,
     ip: String,
     #[serde(rename = "type")]
-    frontend_type: String, // "server" or "website"
+    frontend_type: String, // "server", "website", "port", "ping", "dns" or "service"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    interval_secs: Option<u64>, // overrides POLL_INTERVAL_SECS for this frontend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected_content: Option<String>, // websites only: text the response body must contain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected_status: Option<ExpectedStatus>, // websites only: healthy status codes, 200 if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    method: Option<String>, // websites only: HTTP method, GET if unset (POST with a body)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>, // websites only: request body, sent as JSON unless headers set a Content-Type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    headers: Option<HashMap<String, Secret>>, // websites only: extra request headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    basic_auth_user: Option<String>, // websites only: HTTP basic auth user name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    basic_auth_pass: Option<Secret>, // websites only: HTTP basic auth password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    follow_redirects: Option<bool>, // websites only: false records a 3xx as is, true if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout_secs: Option<u64>, // overrides REQUEST_TIMEOUT for this frontend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    insecure: Option<bool>, // https agents and websites: true accepts any certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reachability_only: Option<bool>, // servers only: true keeps a 2xx without valid metrics out of red
+    #[serde(default)]
+    muted: bool, // still polled, but never alerts
+    #[serde(default, deserialize_with = "deserialize_tags", skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>, // for filtering, e.g. "prod" or "staging"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignore_mounts: Vec<String>, // servers only: mount points left out of disk_status
+}
+
+impl FrontendInfo {
+    // Time allowed for one check, retries included. Clamped as well as
+    // validated, since frontends.json is never validated.
+    fn timeout(&self) -> Duration {
+        self.timeout_secs.map_or(REQUEST_TIMEOUT, |secs| Duration::from_secs(secs.clamp(1, MAX_TIMEOUT_SECS)))
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.unwrap_or(*POLL_INTERVAL_SECS).max(1))
+    }
+
+    // A copy safe to send to clients, with the basic auth password and header
+    // values replaced. Only frontends.json keeps the real ones.
+    fn redacted(&self) -> FrontendInfo {
+        let mut fe = self.clone();
+        if let Some(pass) = &mut fe.basic_auth_pass {
+            *pass = Secret::redacted();
+        }
+        for value in fe.headers.iter_mut().flat_map(HashMap::values_mut) {
+            *value = Secret::redacted();
+        }
+        fe
+    }
+}
+
+// A credential that is saved as is but never shows up in debug output.
+#[derive(Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+struct Secret(String);
+
+impl Secret {
+    const REDACTED: &'static str = "<redacted>";
+
+    fn redacted() -> Secret {
+        Secret(Secret::REDACTED.to_string())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", Secret::REDACTED)
+    }
+}
+
+// Accepts tags as a JSON list or as a comma-separated string, as sent by the
+// add form.
+fn deserialize_tags<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        List(Vec<String>),
+        Text(String),
+    }
+    let tags = match Raw::deserialize(deserializer)? {
+        Raw::List(tags) => tags,
+        Raw::Text(text) => text.split(',').map(str::to_string).collect(),
+    };
+    Ok(tags
+        .into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect())
+}
+
+// Status codes a website may return and still be healthy, written as a single
+// code (`302`) or a comma-separated list of codes and ranges (`"200-299,401"`).
+#[derive(Clone, Debug, PartialEq)]
+struct ExpectedStatus(Vec<RangeInclusive<u16>>);
+
+impl ExpectedStatus {
+    fn matches(&self, code: u16) -> bool {
+        self.0.iter().any(|range| range.contains(&code))
+    }
+}
+
+impl FromStr for ExpectedStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ranges = s
+            .split(',')
+            .map(|part| {
+                let part = part.trim();
+                let (start, end) = part.split_once('-').unwrap_or((part, part));
+                let parse = |code: &str| code.trim().parse::<u16>().map_err(|_| format!("invalid status code '{}'", code.trim()));
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    return Err(format!("invalid status range '{}'", part));
+                }
+                Ok(start..=end)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ExpectedStatus(ranges))
+    }
+}
+
+impl fmt::Display for ExpectedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .map(|range| if range.start() == range.end() {
+                range.start().to_string()
+            } else {
+                format!("{}-{}", range.start(), range.end())
+            })
+            .collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+impl Serialize for ExpectedStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.as_slice() {
+            [range] if range.start() == range.end() => serializer.serialize_u16(*range.start()),
+            _ => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+impl JsonSchema for ExpectedStatus {
+    fn schema_name() -> String {
+        "ExpectedStatus".to_string()
+    }
+
+    // A single status code, or a string such as "200-299,401".
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![gen.subschema_for::<u16>(), gen.subschema_for::<String>()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl<'de> Deserialize<'de> for ExpectedStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Code(u16),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Code(code) => Ok(ExpectedStatus(vec![code..=code])),
+            Raw::Text(text) => text.parse().map_err(de::Error::custom),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct EditFrontend {
+    name: String,
+    ip: String,
+    #[serde(rename = "type")]
+    frontend_type: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -31,13 +283,72 @@ struct DeleteFrontend {
     name: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct MuteFrontend {
+    name: String,
+}
+
+#[derive(Clone, Copy)]
+enum AlertPause {
+    Off,
+    Indefinite,
+    Until(Instant),
+}
+
+// AlertPause as shared between backends, with the deadline in Unix seconds.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SharedAlertPause {
+    Off,
+    Indefinite,
+    Until(u64),
+}
+
+#[derive(Deserialize)]
+struct PauseAlerts {
+    minutes: Option<u64>, // resume automatically after this long; paused until resumed if unset
+}
+
+// Health of a metric or frontend, serialized as the lowercase CSS class the
+// dashboard uses. Variants are ordered from best to worst.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    #[default]
+    Green,
+    Yellow,
+    Red,
+}
+
+impl Status {
+    // The worst of the given statuses, or Green if there are none.
+    fn worst(statuses: impl IntoIterator<Item = Status>) -> Status {
+        statuses.into_iter().max().unwrap_or(Status::Green)
+    }
+
+    // The lowercase name also used in JSON.
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Green => "green",
+            Status::Yellow => "yellow",
+            Status::Red => "red",
+        }
+    }
+}
+
 // Types from the frontend agent.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct DiskUsage {
     mount_point: String,
+    #[serde(default)]
+    filesystem: String, // empty for agents that predate filesystem reporting
     total: u64,
     used: u64,
     used_percent: f64,
+    // None for older agents, filesystems without a fixed inode count and non-Unix hosts.
+    inodes_total: Option<u64>,
+    inodes_used: Option<u64>,
+    inodes_percent: Option<f64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -47,6 +358,53 @@ struct CpuInfo {
     frequency: u64,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+struct NetworkInfo {
+    name: String,
+    received: u64,
+    transmitted: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+struct LoadAverage {
+    one: f64,
+    five: f64,
+    fifteen: f64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+struct DiskIo {
+    read_bytes: u64,    // cumulative since the agent started
+    written_bytes: u64, // cumulative since the agent started
+    read_bytes_per_sec: f64,
+    write_bytes_per_sec: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+struct TempInfo {
+    label: String,
+    celsius: f32,
+}
+
+#[derive(Deserialize)]
+struct ServiceState {
+    state: String,
+}
+
+// Memory in bytes.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+struct GpuInfo {
+    utilization_percent: f32,
+    memory_used: u64,
+    memory_total: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+struct BatteryInfo {
+    percent: f32,
+    on_ac: bool,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct SystemMetrics {
     disk_usage: Vec<DiskUsage>,
@@ -55,73 +413,703 @@ struct SystemMetrics {
     total_memory: u64,
     used_memory: u64,
     memory_percent: f64,
+    #[serde(default)]
+    total_swap: u64,
+    #[serde(default)]
+    used_swap: u64,
+    #[serde(default)]
+    swap_percent: f64, // zero when no swap is configured
+    #[serde(default)]
+    networks: Vec<NetworkInfo>, // empty for agents that predate network metrics
+    #[serde(default)]
+    load_avg: LoadAverage, // zeros for older agents and on Windows
+    #[serde(default)]
+    disk_io: DiskIo,
+    #[serde(default)]
+    temperatures: Vec<TempInfo>, // empty without thermal sensors
+    #[serde(default)]
+    hostname: String,
+    #[serde(default)]
+    os_name: String,
+    #[serde(default)]
+    os_version: String,
+    #[serde(default)]
+    kernel_version: String,
+    #[serde(default)]
+    battery: Option<BatteryInfo>, // None without a battery and for older agents
+    #[serde(default)]
+    users: Vec<String>, // logged-in users, empty for older agents
+    #[serde(default)]
+    gpus: Vec<GpuInfo>, // empty without NVIDIA GPUs, for agents built without `gpu` and older agents
+    #[serde(default)]
+    version: Option<String>, // None for agents that predate versioning
 }
 
 // Computed types.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 struct ComputedDiskUsage {
     mount_point: String,
+    filesystem: String,
     total: u64,
     used: u64,
+    #[serde(default)]
+    total_human: String, // e.g. "1.8 TiB"
+    #[serde(default)]
+    used_human: String,
     used_percent: f64,
-    status: String, // "red" if used_percent > 90, else "green"
+    status: Status, // from used_percent against DISK_THRESHOLDS
+    inodes_total: Option<u64>,
+    inodes_used: Option<u64>,
+    inodes_percent: Option<f64>,
+    inode_status: Option<Status>, // from inodes_percent against INODE_THRESHOLDS
+    #[serde(default)]
+    ignored: bool, // listed in the frontend's ignore_mounts, so not part of disk_status
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 struct ComputedCpuInfo {
     name: String,
     cpu_usage: f32,
     frequency: u64,
-    status: String, // "red" if cpu_usage > 90, else "green"
+    status: Status, // from cpu_usage against CPU_THRESHOLDS
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 struct ComputedMemoryUsage {
     total_memory: u64,
     used_memory: u64,
+    #[serde(default)]
+    total_human: String,
+    #[serde(default)]
+    used_human: String,
     memory_percent: f64,
-    status: String, // "red" if memory_percent > 90, else "green"
+    status: Status, // from memory_percent against MEMORY_THRESHOLDS
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+struct ComputedSwapUsage {
+    total_swap: u64,
+    used_swap: u64,
+    #[serde(default)]
+    total_human: String,
+    #[serde(default)]
+    used_human: String,
+    swap_percent: f64,
+    status: Status, // from swap_percent against SWAP_THRESHOLDS
+}
+
+// Identifies the machine an agent runs on.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+struct HostInfo {
+    hostname: String,
+    os_name: String,
+    os_version: String,
+    kernel_version: String,
 }
 
 // For website status history.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 struct StatusRecord {
     status_code: u16,
     crawl_time: String,
+    #[serde(default)]
+    response_ms: Option<u64>, // None if the request failed
+}
+
+// For the CPU and memory sparkline of server frontends.
+#[derive(Clone, Debug, Serialize)]
+struct ServerSample {
+    crawl_time: String,
+    cpu_usage: f32,
+    memory_percent: f64,
+    disk_max: Option<f64>, // used percent of the fullest disk, None without disks
 }
 
 // ServerUsage now includes a connectivity field.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 struct ServerUsage {
     frontend: FrontendInfo,
+    /// Only for server type.
     disk_usage: Option<Vec<ComputedDiskUsage>>,
+    /// Only for server type: global CPU usage percentage.
     cpu_usage: Option<f32>,
+    /// Only for server type.
     cpus: Option<Vec<ComputedCpuInfo>>,
+    /// Only for server type.
     memory_usage: Option<ComputedMemoryUsage>,
-    disk_status: String,    // "red" if any disk is red, else "green"
-    cpu_status: String,     // "red" if global CPU usage > 90, else "green"
-    memory_status: String,  // "red" if memory usage > 90, else "green"
-    overall_status: String, // "red" if any of the statuses is red, else "green"
-    connectivity: String,   // "green" if reachable, "red" otherwise
-    crawl_time: String,     // crawl time in Thailand time (UTC+7)
-    status_history: Option<Vec<StatusRecord>>, // Only for website type
+    /// Only for server type.
+    swap_usage: Option<ComputedSwapUsage>,
+    /// Only for server type: per interface, loopback included.
+    networks: Option<Vec<NetworkInfo>>,
+    /// Only for server type.
+    load_avg: Option<LoadAverage>,
+    /// Only for server type.
+    disk_io: Option<DiskIo>,
+    /// Only for server type.
+    temperatures: Option<Vec<TempInfo>>,
+    /// Only for server type with a battery.
+    battery: Option<BatteryInfo>,
+    /// Only for server type: logged-in users, informational only.
+    users: Option<Vec<String>>,
+    /// Only for server type: NVIDIA GPUs, empty if the agent reports none.
+    gpus: Option<Vec<GpuInfo>>,
+    /// Only for server type.
+    host: Option<HostInfo>,
+    /// Only for server type, None for agents that predate versioning.
+    agent_version: Option<String>,
+    /// Worst status of any disk.
+    #[serde(default)]
+    disk_status: Status,
+    /// Worst inode usage of any disk, Green without inode data.
+    #[serde(default)]
+    inode_status: Status,
+    /// From global CPU usage against CPU_THRESHOLDS.
+    #[serde(default)]
+    cpu_status: Status,
+    /// From memory usage against MEMORY_THRESHOLDS.
+    #[serde(default)]
+    memory_status: Status,
+    /// From swap usage against SWAP_THRESHOLDS.
+    #[serde(default)]
+    swap_status: Status,
+    /// Hottest sensor against TEMP_THRESHOLDS, Green without sensors.
+    #[serde(default)]
+    temp_status: Status,
+    /// Red on battery power or below BATTERY_THRESHOLD, Green without a battery.
+    #[serde(default)]
+    battery_status: Status,
+    /// Busiest GPU against GPU_THRESHOLDS, Green without GPUs.
+    #[serde(default)]
+    gpu_status: Status,
+    /// The worst of the statuses; yellow means degraded.
+    #[serde(default)]
+    overall_status: Status,
+    /// Green if reachable, Red otherwise.
+    #[serde(default)]
+    connectivity: Status,
+    /// Crawl time in the TZ_OFFSET_HOURS timezone.
+    crawl_time: String,
+    /// Only for website type.
+    status_history: Option<Vec<StatusRecord>>,
+    /// Only for https websites.
+    cert_days_remaining: Option<i64>,
+    /// Only for ping type: round-trip time of the echo reply.
+    ping_ms: Option<f64>,
+    /// Only for dns type: addresses the name resolved to.
+    resolved_ips: Option<Vec<String>>,
+    /// Only for service type: the unit's state, e.g. "active" or "failed".
+    service_state: Option<String>,
+    /// Only for server and service types: why the agent could not be polled, one of
+    /// "bad_address", "unreachable", "bad_status" or "parse_error", or "no_metrics" when
+    /// a reachability_only agent answered without valid metrics.
+    error_kind: Option<String>,
+    /// Share of non-red polls within UPTIME_WINDOW_SECS.
+    #[serde(default)]
+    uptime_percent: f64,
+    /// Overall status changed more than FLAP_THRESHOLD times within FLAP_WINDOW_SECS.
+    #[serde(default)]
+    flapping: bool,
+    /// The crawl time is older than STALE_AFTER_INTERVALS poll intervals.
+    #[serde(default)]
+    stale: bool,
+}
+
+impl ServerUsage {
+    // A record without metrics where every status is the given one.
+    fn with_status(fe: &FrontendInfo, crawl_time: &str, status: Status) -> Self {
+        ServerUsage {
+            frontend: fe.redacted(),
+            disk_usage: None,
+            cpu_usage: None,
+            cpus: None,
+            memory_usage: None,
+            swap_usage: None,
+            networks: None,
+            load_avg: None,
+            disk_io: None,
+            temperatures: None,
+            battery: None,
+            users: None,
+            gpus: None,
+            host: None,
+            agent_version: None,
+            disk_status: status,
+            inode_status: status,
+            cpu_status: status,
+            memory_status: status,
+            swap_status: status,
+            temp_status: status,
+            battery_status: status,
+            gpu_status: status,
+            overall_status: status,
+            connectivity: status,
+            crawl_time: crawl_time.to_string(),
+            status_history: None,
+            cert_days_remaining: None,
+            ping_ms: None,
+            resolved_ips: None,
+            service_state: None,
+            error_kind: None,
+            uptime_percent: 100.0,
+            flapping: false,
+            stale: false,
+        }
+    }
+
+    // A fully red record for a frontend whose metrics could not be collected.
+    fn failed(fe: &FrontendInfo, crawl_time: &str, connectivity: Status) -> Self {
+        ServerUsage { connectivity, ..ServerUsage::with_status(fe, crawl_time, Status::Red) }
+    }
+}
+
+// An outage of one status key, open until `ended` is set.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Incident {
+    frontend: String,
+    metric: String,
+    started: String,
+    ended: Option<String>,
+}
+
+// A single poll outcome; `up` is true unless the frontend was red.
+struct UptimeSample {
+    at: Instant,
+    up: bool,
+}
+
+// Recent red/not-red transitions of a frontend, for flap detection.
+#[derive(Default)]
+struct FlapState {
+    transitions: VecDeque<Instant>,
+    flapping: bool,
 }
 
 // Global in‑memory storage.
-static FRONTENDS: Lazy<RwLock<Vec<FrontendInfo>>> = Lazy::new(|| {
-    let frontends = load_frontends().unwrap_or_else(|_| vec![]);
-    RwLock::new(frontends)
+// Frontends and their latest usage are keyed by name, in configured order.
+type Frontends = IndexMap<String, FrontendInfo>;
+type UsageData = IndexMap<String, ServerUsage>;
+
+// Behind an Arc, so the poll loop can take a snapshot each tick without
+// copying every frontend. Writers copy it only if a snapshot is still held.
+static FRONTENDS: Lazy<RwLock<Arc<Frontends>>> = Lazy::new(|| {
+    let frontends = load_frontends().unwrap_or_else(|e| {
+        if e.kind() != io::ErrorKind::NotFound {
+            error!(file = FRONTENDS_FILE, error = %e, "Failed to load frontends, starting without them");
+            FRONTENDS_UNREADABLE.store(true, Ordering::Relaxed);
+        }
+        vec![]
+    });
+    RwLock::new(Arc::new(frontends.into_iter().map(|fe| (fe.name.clone(), fe)).collect()))
+});
+// Replaced wholesale after each poll, so readers can hold on to a snapshot
+// without copying it.
+// Starts from the results saved before the last shutdown, if any, so the
+// dashboard has something to show until the first poll cycle finishes.
+static USAGE_DATA: Lazy<RwLock<Arc<UsageData>>> = Lazy::new(|| {
+    let usage_data = load_usage_cache().unwrap_or_else(|e| {
+        if e.kind() != io::ErrorKind::NotFound {
+            warn!(file = USAGE_CACHE_FILE, error = %e, "Failed to load the usage cache, starting without it");
+        }
+        IndexMap::new()
+    });
+    RwLock::new(Arc::new(usage_data))
+});
+// Each new USAGE_DATA snapshot, for /api/stream subscribers. Subscribers only
+// need the latest snapshot, so a lagging one just skips ahead.
+static USAGE_UPDATES: Lazy<broadcast::Sender<Arc<UsageData>>> = Lazy::new(|| broadcast::channel(16).0);
+// Set while frontends.json holds something that failed to load, so shutdown
+// doesn't replace it with the empty list this backend started with. Cleared
+// once a change to the frontends is saved over it.
+static FRONTENDS_UNREADABLE: AtomicBool = AtomicBool::new(false);
+// Set once the first poll cycle has filled USAGE_DATA, for /ready.
+static READY: AtomicBool = AtomicBool::new(false);
+static WEBSITE_HISTORY: Lazy<RwLock<HashMap<String, VecDeque<StatusRecord>>>> = Lazy::new(|| {
+    let history = load_website_history().unwrap_or_else(|_| HashMap::new());
+    RwLock::new(history)
+});
+// Kept in memory only; it refills within a few polls after a restart.
+static SERVER_HISTORY: Lazy<RwLock<HashMap<String, VecDeque<ServerSample>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+// Long-term metrics and website status history, when DB_PATH is set. Without it
+// only the in-memory histories above are kept.
+static DB: Lazy<Option<Mutex<Connection>>> = Lazy::new(|| {
+    let path = env::var("DB_PATH").ok().filter(|path| !path.is_empty())?;
+    match open_db(&path) {
+        Ok(conn) => {
+            info!(path = %path, "Storing history in SQLite");
+            Some(Mutex::new(conn))
+        }
+        Err(e) => {
+            error!(path = %path, error = %e, "Failed to open DB_PATH, keeping history in memory only");
+            None
+        }
+    }
+});
+// Shares frontends, the latest usage and alert state between backends behind
+// a load balancer, of which only the one holding the poller lock polls.
+// Without REDIS_URL all state lives in memory.
+static REDIS: Lazy<Option<redis::Client>> = Lazy::new(|| {
+    let url = env::var("REDIS_URL").ok().filter(|url| !url.is_empty())?;
+    match redis::Client::open(url.as_str()) {
+        Ok(client) => Some(client),
+        // The URL may contain a password, so it is not logged.
+        Err(e) => {
+            error!(error = %e, "Invalid REDIS_URL value, keeping state in memory only");
+            None
+        }
+    }
+});
+static REDIS_CONNECTION: OnceCell<ConnectionManager> = OnceCell::const_new();
+// Set while this backend holds the poller lock, as of its last renewal.
+static POLLER: AtomicBool = AtomicBool::new(false);
+// Identifies this backend as the holder of the poller lock.
+static INSTANCE_ID: Lazy<String> = Lazy::new(|| {
+    let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{}-{}", std::process::id(), started.as_nanos())
+});
+// Takes the poller lock if it is free and renews it if this backend holds it.
+static POLLER_LOCK_SCRIPT: Lazy<redis::Script> = Lazy::new(|| {
+    redis::Script::new(
+        r"if redis.call('GET', KEYS[1]) == ARGV[1] then
+              return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+          end
+          if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+              return 1
+          end
+          return 0",
+    )
+});
+// Stores the frontends passed as name and JSON pairs, unless the hash exists.
+static SEED_FRONTENDS_SCRIPT: Lazy<redis::Script> = Lazy::new(|| {
+    redis::Script::new(
+        r"if #ARGV == 0 or redis.call('EXISTS', KEYS[1]) == 1 then
+              return 0
+          end
+          redis.call('HSET', KEYS[1], unpack(ARGV))
+          return 1",
+    )
+});
+static POLLER_RELEASE_SCRIPT: Lazy<redis::Script> = Lazy::new(|| {
+    redis::Script::new(
+        r"if redis.call('GET', KEYS[1]) == ARGV[1] then
+              return redis.call('DEL', KEYS[1])
+          end
+          return 0",
+    )
+});
+static INCIDENTS: Lazy<RwLock<Vec<Incident>>> = Lazy::new(|| {
+    let incidents = load_incidents(INCIDENTS_FILE).unwrap_or_else(|_| vec![]);
+    RwLock::new(incidents)
 });
-static USAGE_DATA: Lazy<RwLock<Vec<ServerUsage>>> = Lazy::new(|| RwLock::new(vec![]));
-static WEBSITE_HISTORY: Lazy<RwLock<HashMap<String, Vec<StatusRecord>>>> =
+// Last seen status per frontend, keyed by frontend name and then status key,
+// so alerts fire only when a status turns red. Starts out red for every open
+// incident, so a frontend still red after a restart doesn't open another one.
+static LAST_STATUSES: Lazy<RwLock<HashMap<String, HashMap<String, Status>>>> = Lazy::new(|| {
+    let mut last_statuses: HashMap<String, HashMap<String, Status>> = HashMap::new();
+    for incident in INCIDENTS.read_or_recover().iter().filter(|i| i.ended.is_none()) {
+        last_statuses.entry(incident.frontend.clone()).or_default().insert(incident.metric.clone(), Status::Red);
+    }
+    RwLock::new(last_statuses)
+});
+// Timestamped poll outcomes per frontend, for uptime.
+static UPTIME_SAMPLES: Lazy<RwLock<HashMap<String, VecDeque<UptimeSample>>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
+// Whether every alert is paused, e.g. during network maintenance. Statuses
+// and incidents are still recorded.
+static ALERT_PAUSE: Lazy<RwLock<AlertPause>> = Lazy::new(|| RwLock::new(AlertPause::Off));
+static FLAP_STATES: Lazy<RwLock<HashMap<String, FlapState>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+// When each frontend last sent a red alert, for ALERT_COOLDOWN_SECS.
+static LAST_ALERTS: Lazy<RwLock<HashMap<String, Instant>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+// Sends every outbound request through this proxy, except for hosts listed in
+// NO_PROXY. Without it reqwest still honors HTTP_PROXY and HTTPS_PROXY.
+static PROXY: Lazy<Option<Proxy>> = Lazy::new(|| {
+    let url = env::var("PROXY_URL").ok().filter(|url| !url.is_empty())?;
+    match Proxy::all(&url) {
+        Ok(proxy) => Some(proxy.no_proxy(NoProxy::from_env())),
+        // The URL may contain credentials, so it is not logged.
+        Err(e) => {
+            warn!(error = %e, "Invalid PROXY_URL value, falling back to HTTP_PROXY and HTTPS_PROXY");
+            None
+        }
+    }
+});
+
+// The identity every outbound request presents. Some WAFs block requests
+// without a recognizable User-Agent.
+static USER_AGENT: Lazy<HeaderValue> = Lazy::new(|| {
+    let default = HeaderValue::from_static(concat!("rust-server-monitor/", env!("CARGO_PKG_VERSION")));
+    match env::var("USER_AGENT") {
+        Ok(val) if !val.trim().is_empty() => HeaderValue::from_str(val.trim()).unwrap_or_else(|_| {
+            warn!(value = %val, "Invalid USER_AGENT value, falling back to the default");
+            default
+        }),
+        _ => default,
+    }
+});
 
+// Logs every alert instead of sending it, to check alert logic and messages
+// before pointing the backend at real channels.
+static ALERT_DRY_RUN: Lazy<bool> = Lazy::new(|| {
+    env::var("ALERT_DRY_RUN").map(|val| val.to_lowercase() == "true").unwrap_or(false)
+});
 static SLACK_WEBHOOK: Lazy<Option<String>> = Lazy::new(|| {
     env::var("SLACK_WEBHOOK").ok()
 });
 static SLACK_ALERT_ENABLED: Lazy<bool> = Lazy::new(|| {
     env::var("SLACK_ALERT").map(|val| val.to_lowercase() == "true").unwrap_or(false)
 });
+static API_KEY: Lazy<Option<String>> = Lazy::new(|| {
+    env::var("API_KEY").ok().filter(|key| !key.is_empty())
+});
+// Sent as a bearer token to server agents that require one.
+static AGENT_TOKEN: Lazy<Option<String>> = Lazy::new(|| {
+    env::var("AGENT_TOKEN").ok().filter(|token| !token.is_empty())
+});
+static DASHBOARD_CREDENTIALS: Lazy<Option<(String, String)>> = Lazy::new(|| {
+    match (env::var("DASHBOARD_USER"), env::var("DASHBOARD_PASS")) {
+        (Ok(user), Ok(pass)) => Some((user, pass)),
+        _ => None,
+    }
+});
+static METRICS_PUBLIC: Lazy<bool> = Lazy::new(|| {
+    env::var("METRICS_PUBLIC").map(|val| val.to_lowercase() == "true").unwrap_or(false)
+});
+static DISCORD_WEBHOOK: Lazy<Option<String>> = Lazy::new(|| {
+    env::var("DISCORD_WEBHOOK").ok()
+});
+static DISCORD_ALERT_ENABLED: Lazy<bool> = Lazy::new(|| {
+    env::var("DISCORD_ALERT").map(|val| val.to_lowercase() == "true").unwrap_or(false)
+});
+static TEAMS_WEBHOOK: Lazy<Option<String>> = Lazy::new(|| {
+    env::var("TEAMS_WEBHOOK").ok()
+});
+static TEAMS_ALERT_ENABLED: Lazy<bool> = Lazy::new(|| {
+    env::var("TEAMS_ALERT").map(|val| val.to_lowercase() == "true").unwrap_or(false)
+});
+static TELEGRAM_BOT_TOKEN: Lazy<Option<String>> = Lazy::new(|| {
+    env::var("TELEGRAM_BOT_TOKEN").ok().filter(|token| !token.is_empty())
+});
+static TELEGRAM_CHAT_ID: Lazy<Option<String>> = Lazy::new(|| {
+    env::var("TELEGRAM_CHAT_ID").ok().filter(|id| !id.is_empty())
+});
+static TELEGRAM_ALERT_ENABLED: Lazy<bool> = Lazy::new(|| {
+    env::var("TELEGRAM_ALERT").map(|val| val.to_lowercase() == "true").unwrap_or(false)
+});
+
+struct EmailAlerter {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+// Email alerts are enabled only when SMTP_HOST, ALERT_EMAIL_FROM and
+// ALERT_EMAIL_TO are all set; SMTP_USER/SMTP_PASS are optional.
+static EMAIL_ALERTER: Lazy<Option<EmailAlerter>> = Lazy::new(|| {
+    let host = env::var("SMTP_HOST").ok()?;
+    let from = env::var("ALERT_EMAIL_FROM").ok()?;
+    let to = env::var("ALERT_EMAIL_TO").ok()?;
+    let port = env_parse::<u16>("SMTP_PORT", 587);
+    let from: Mailbox = match from.parse() {
+        Ok(mailbox) => mailbox,
+        Err(e) => {
+            warn!(error = %e, "Invalid ALERT_EMAIL_FROM, email alerts disabled");
+            return None;
+        }
+    };
+    let to: Vec<Mailbox> = match to.split(',').map(|addr| addr.trim().parse()).collect() {
+        Ok(mailboxes) => mailboxes,
+        Err(e) => {
+            warn!(error = %e, "Invalid ALERT_EMAIL_TO, email alerts disabled");
+            return None;
+        }
+    };
+    let mut builder = match AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host) {
+        Ok(builder) => builder.port(port),
+        Err(e) => {
+            warn!(error = %e, "Invalid SMTP_HOST, email alerts disabled");
+            return None;
+        }
+    };
+    if let (Ok(user), Ok(pass)) = (env::var("SMTP_USER"), env::var("SMTP_PASS")) {
+        builder = builder.credentials(Credentials::new(user, pass));
+    }
+    Some(EmailAlerter { mailer: builder.build(), from, to })
+});
+
+struct WebhookAlerter {
+    url: String,
+    template: String,
+}
+
+const DEFAULT_WEBHOOK_TEMPLATE: &str =
+    r#"{"frontend": "{frontend}", "status": "{status}", "time": "{time}", "message": "{message}"}"#;
+
+// Generic webhook alerts are enabled when WEBHOOK_URL is set. WEBHOOK_TEMPLATE
+// must render to valid JSON, otherwise the channel is disabled.
+static WEBHOOK_ALERTER: Lazy<Option<WebhookAlerter>> = Lazy::new(|| {
+    let url = env::var("WEBHOOK_URL").ok().filter(|url| !url.is_empty())?;
+    let template = env::var("WEBHOOK_TEMPLATE").unwrap_or_else(|_| DEFAULT_WEBHOOK_TEMPLATE.to_string());
+    let sample = render_webhook_template(&template, "message", "frontend", Status::Red, "time");
+    if let Err(e) = serde_json::from_str::<serde_json::Value>(&sample) {
+        error!(error = %e, "WEBHOOK_TEMPLATE is not valid JSON, webhook alerts disabled");
+        return None;
+    }
+    Some(WebhookAlerter { url, template })
+});
+
+// Closed incidents beyond this many are moved to monthly archive files. 0
+// keeps every incident in incidents.json.
+static MAX_INCIDENTS: Lazy<usize> = Lazy::new(|| env_parse::<usize>("MAX_INCIDENTS", 1000));
+
+// Minimum time between red alerts for the same frontend. 0 disables the cooldown.
+static ALERT_COOLDOWN_SECS: Lazy<u64> = Lazy::new(|| env_parse::<u64>("ALERT_COOLDOWN_SECS", 0));
+
+// A frontend whose overall status turns red or back more than FLAP_THRESHOLD
+// times within FLAP_WINDOW_SECS is flapping: it sends one alert when that
+// starts and one when it settles, instead of one per change. 0 disables it.
+static FLAP_THRESHOLD: Lazy<usize> = Lazy::new(|| env_parse::<usize>("FLAP_THRESHOLD", 4));
+static FLAP_WINDOW_SECS: Lazy<u64> = Lazy::new(|| env_parse::<u64>("FLAP_WINDOW_SECS", 600));
+
+// Percentages above which a metric is reported as "yellow" and "red".
+const DEFAULT_THRESHOLD: f64 = 90.0;
+// Degrees Celsius above which a temperature sensor is reported as "red".
+const DEFAULT_TEMP_THRESHOLD: f64 = 80.0;
+static DISK_THRESHOLDS: Lazy<Thresholds> = Lazy::new(|| Thresholds::from_env("DISK", DEFAULT_THRESHOLD));
+static INODE_THRESHOLDS: Lazy<Thresholds> = Lazy::new(|| Thresholds::from_env("INODE", DEFAULT_THRESHOLD));
+static CPU_THRESHOLDS: Lazy<Thresholds> = Lazy::new(|| Thresholds::from_env("CPU", DEFAULT_THRESHOLD));
+static MEMORY_THRESHOLDS: Lazy<Thresholds> = Lazy::new(|| Thresholds::from_env("MEMORY", DEFAULT_THRESHOLD));
+static SWAP_THRESHOLDS: Lazy<Thresholds> = Lazy::new(|| Thresholds::from_env("SWAP", DEFAULT_THRESHOLD));
+static TEMP_THRESHOLDS: Lazy<Thresholds> = Lazy::new(|| Thresholds::from_env("TEMP", DEFAULT_TEMP_THRESHOLD));
+static GPU_THRESHOLDS: Lazy<Thresholds> = Lazy::new(|| Thresholds::from_env("GPU", DEFAULT_THRESHOLD));
+// Charge percentage below which a battery is reported as "red". Running on
+// battery at all is red too, since it usually means a power cut.
+static BATTERY_THRESHOLD: Lazy<f32> = Lazy::new(|| env_parse::<f32>("BATTERY_THRESHOLD", 20.0));
+
+fn battery_status(battery: Option<&BatteryInfo>) -> Status {
+    match battery {
+        Some(battery) if !battery.on_ac || battery.percent < *BATTERY_THRESHOLD => Status::Red,
+        _ => Status::Green,
+    }
+}
+
+// Websites whose TLS certificate expires within this many days are reported red.
+static CERT_WARN_DAYS: Lazy<i64> = Lazy::new(|| env_parse::<i64>("CERT_WARN_DAYS", 14));
+
+// Rolling window over which uptime_percent is computed.
+static UPTIME_WINDOW_SECS: Lazy<u64> = Lazy::new(|| env_parse::<u64>("UPTIME_WINDOW_SECS", 86400).max(1));
+
+// Number of status records kept per website.
+static HISTORY_LENGTH: Lazy<usize> = Lazy::new(|| env_parse::<usize>("HISTORY_LENGTH", 3).max(1));
+
+// Number of CPU and memory samples kept per server.
+static SERVER_HISTORY_LENGTH: Lazy<usize> = Lazy::new(|| env_parse::<usize>("SERVER_HISTORY_LENGTH", 60).max(1));
+
+// Agents older than this are logged as outdated, once per agent.
+static MIN_AGENT_VERSION: Lazy<Option<[u64; 3]>> = Lazy::new(|| {
+    let val = env::var("MIN_AGENT_VERSION").ok().filter(|val| !val.trim().is_empty())?;
+    let version = parse_version(&val);
+    if version.is_none() {
+        warn!(value = %val, "Invalid MIN_AGENT_VERSION value, ignoring it");
+    }
+    version
+});
+// Agents currently logged as outdated, so each is only warned about once.
+static OUTDATED_AGENTS: Lazy<RwLock<HashSet<String>>> = Lazy::new(|| RwLock::new(HashSet::new()));
+
+// Upper bound on how much of a website's body is read for content matching.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+// Number of times a failed request is retried before a frontend is marked down.
+static POLL_RETRIES: Lazy<u32> = Lazy::new(|| env_parse::<u32>("POLL_RETRIES", 2));
+// Delay before the first retry, doubled for each one after.
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+// Timezone used for crawl times, as a whole-hour offset from UTC.
+static TZ_OFFSET: Lazy<FixedOffset> = Lazy::new(|| {
+    let hours = env_parse::<i32>("TZ_OFFSET_HOURS", 0);
+    hours.checked_mul(3600).and_then(FixedOffset::east_opt).unwrap_or_else(|| {
+        warn!(hours, "Invalid TZ_OFFSET_HOURS value, falling back to 0");
+        FixedOffset::east_opt(0).unwrap()
+    })
+});
+
+// Port the frontend agent listens on, used when a server's address has none.
+const AGENT_PORT: u16 = 8081;
+
+// How many frontends are polled at once.
+static POLL_CONCURRENCY: Lazy<usize> = Lazy::new(|| env_parse::<usize>("POLL_CONCURRENCY", 100).max(1));
+
+// Timeout for every outbound check, unless a frontend sets timeout_secs.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Longest timeout_secs a frontend may set.
+const MAX_TIMEOUT_SECS: u64 = 3600;
+// Longest interval_secs a frontend may set.
+const MAX_INTERVAL_SECS: u64 = 86_400;
+
+// How often the poll loop checks which frontends are due.
+const POLL_TICK: Duration = Duration::from_secs(1);
+// Minimum time between usage cache writes. The cache only has to be recent
+// enough to fill the dashboard after a restart, and shutdown saves it anyway.
+const USAGE_CACHE_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+// Longest /api/stream goes without sending anything, so idle proxies don't
+// cut the connection between poll cycles.
+const STREAM_KEEPALIVE: Duration = Duration::from_secs(15);
+// crawl_time, in the TZ_OFFSET_HOURS timezone.
+const CRAWL_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+// A frontend not polled for this many of its intervals, plus its timeout, is
+// flagged as stale, which usually means the poll loop is stuck. 0 disables it.
+static STALE_AFTER_INTERVALS: Lazy<u32> = Lazy::new(|| env_parse::<u32>("STALE_AFTER_INTERVALS", 3));
+static POLL_INTERVAL_SECS: Lazy<u64> = Lazy::new(|| env_parse::<u64>("POLL_INTERVAL_SECS", 5).max(1));
+
+struct Thresholds {
+    warn: f64,
+    crit: f64,
+}
+
+impl Thresholds {
+    // Reads `{prefix}_CRIT` (or the older `{prefix}_THRESHOLD`) and `{prefix}_WARN`.
+    // Without a warn level the yellow tier is disabled.
+    fn from_env(prefix: &str, default_crit: f64) -> Self {
+        let crit = env_threshold(&format!("{}_CRIT", prefix))
+            .or_else(|| env_threshold(&format!("{}_THRESHOLD", prefix)))
+            .unwrap_or(default_crit);
+        let warn = env_threshold(&format!("{}_WARN", prefix)).unwrap_or(crit);
+        Thresholds { warn, crit }
+    }
+
+    fn status(&self, value: f64) -> Status {
+        if value > self.crit {
+            Status::Red
+        } else if value > self.warn {
+            Status::Yellow
+        } else {
+            Status::Green
+        }
+    }
+}
+
+// A setting from the environment, or `default` when it is unset or invalid.
+fn env_parse<T: FromStr + fmt::Display>(key: &str, default: T) -> T {
+    let Ok(val) = env::var(key) else {
+        return default;
+    };
+    val.trim().parse().unwrap_or_else(|_| {
+        warn!(value = %val, "Invalid {} value, falling back to {}", key, default);
+        default
+    })
+}
+
+fn env_threshold(key: &str) -> Option<f64> {
+    let val = env::var(key).ok()?;
+    match val.trim().parse::<f64>() {
+        Ok(threshold) => Some(threshold),
+        Err(_) => {
+            warn!(key, value = %val, "Invalid threshold, ignoring it");
+            None
+        }
+    }
+}
 
 fn load_frontends() -> std::io::Result<Vec<FrontendInfo>> {
     let mut file = File::open(FRONTENDS_FILE)?;
@@ -131,272 +1119,1482 @@ fn load_frontends() -> std::io::Result<Vec<FrontendInfo>> {
     Ok(frontends)
 }
 
-fn save_frontends(frontends: &Vec<FrontendInfo>) -> std::io::Result<()> {
-    let data = serde_json::to_string_pretty(frontends)?;
-    let mut file = File::create(FRONTENDS_FILE)?;
-    file.write_all(data.as_bytes())?;
+fn save_frontends(frontends: &Frontends) -> Result<(), AppError> {
+    let frontends: Vec<&FrontendInfo> = frontends.values().collect();
+    save_json(FRONTENDS_FILE, &frontends)?;
+    FRONTENDS_UNREADABLE.store(false, Ordering::Relaxed);
     Ok(())
 }
 
-#[get("/api/servers")]
-async fn api_servers() -> impl Responder {
-    let usage_data = USAGE_DATA.read().unwrap().clone();
-    HttpResponse::Ok().json(usage_data)
+fn load_website_history() -> std::io::Result<HashMap<String, VecDeque<StatusRecord>>> {
+    let mut file = File::open(HISTORY_FILE)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+    let history = serde_json::from_str(&data)?;
+    Ok(history)
 }
 
-#[get("/")]
-async fn index() -> impl Responder {
-    // The HTML page remains unchanged.
-    let html = r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-  <meta charset="UTF-8">
-  <title>Monitoring Dashboard</title>
-  <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0/dist/css/bootstrap.min.css" rel="stylesheet">
-  <style>
-    body { padding: 20px; }
-    .server-container { border: 1px solid #dee2e6; border-radius: 0.25rem; padding: 15px; margin-bottom: 15px; }
-    .server-header { display: flex; justify-content: space-between; align-items: center; }
-    .status-label { margin-left: 10px; font-weight: bold; }
-    .green { color: green; }
-    .red { color: red; }
-    .tab-group { margin-top: 10px; }
-    .tab-item { margin-bottom: 10px; }
-    .tab { cursor: pointer; padding: 5px 10px; border: 1px solid #dee2e6; border-radius: 0.25rem; background-color: #f8f9fa; margin-right: 5px; }
-    .tab:hover { background-color: #e9ecef; }
-    .tab-content { margin-top: 5px; display: none; }
-  </style>
-</head>
-<body>
-  <div class="container">
-    <h1 class="mb-4">Monitoring Dashboard</h1>
-    <div id="alert-container"></div>
-    <button id="addFrontendBtn" class="btn btn-primary mb-3">Add New Frontend</button>
-    <div id="servers"></div>
-  </div>
+// Drops history for frontends that are no longer configured websites and
+// reports whether any was dropped.
+fn prune_website_history(frontends: &Frontends) -> bool {
+    let mut history = WEBSITE_HISTORY.write_or_recover();
+    let before = history.len();
+    history.retain(|name, _| {
+        frontends.get(name).is_some_and(|f| f.frontend_type.to_lowercase() == "website")
+    });
+    history.len() != before
+}
 
-  <!-- Add Frontend Modal -->
-  <div class="modal fade" id="addFrontendModal" tabindex="-1" aria-labelledby="addFrontendModalLabel" aria-hidden="true">
-    <div class="modal-dialog">
-      <div class="modal-content">
-        <form id="add-frontend-form">
-          <div class="modal-header">
-            <h5 class="modal-title" id="addFrontendModalLabel">Add New Frontend</h5>
-            <button type="button" class="btn-close" data-bs-dismiss="modal" aria-label="Close"></button>
-          </div>
-          <div class="modal-body">
-            <div class="mb-3">
-              <label for="frontendName" class="form-label">Server Name</label>
-              <input type="text" class="form-control" id="frontendName" name="name" required>
-            </div>
-            <div class="mb-3">
-              <label for="frontendIP" class="form-label">IP/Address</label>
-              <input type="text" class="form-control" id="frontendIP" name="ip" required>
-            </div>
-            <div class="mb-3">
-              <label for="frontendType" class="form-label">Type</label>
-              <select class="form-select" id="frontendType" name="type" required>
-                <option value="server">Server</option>
-                <option value="website">Website</option>
-              </select>
-            </div>
-          </div>
-          <div class="modal-footer">
-            <button type="button" class="btn btn-secondary" data-bs-dismiss="modal">Cancel</button>
-            <button type="submit" class="btn btn-primary">Add Frontend</button>
-          </div>
-        </form>
-      </div>
-    </div>
-  </div>
+fn save_website_history(frontends: &Frontends) -> Result<(), AppError> {
+    // The poll loop saves in the background and shutdown saves once more.
+    // Snapshot under this lock, so an older snapshot never overwrites a newer one.
+    static SAVING: Mutex<()> = Mutex::new(());
+    let _saving = SAVING.write_or_recover();
+    prune_website_history(frontends);
+    let history = WEBSITE_HISTORY.read_or_recover().clone();
+    save_json(HISTORY_FILE, &history)
+}
 
-  <script src="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0/dist/js/bootstrap.bundle.min.js"></script>
-  <script>
-    // Global object for expanded states.
-    window.expandedStates = {};
+fn load_incidents(path: &str) -> std::io::Result<Vec<Incident>> {
+    let mut file = File::open(path)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+    let incidents = serde_json::from_str(&data)?;
+    Ok(incidents)
+}
 
-    function computeTimeDisplay(crawlTimeString) {
-      let crawlTimeISO = crawlTimeString.replace(" ", "T");
-      let crawlTime = new Date(crawlTimeISO);
-      let now = new Date();
-      let diffSeconds = Math.floor((now - crawlTime) / 1000);
-      return diffSeconds === 0 ? "(Just now)" : `(${diffSeconds} seconds ago)`;
+fn save_incidents(incidents: &[Incident]) -> Result<(), AppError> {
+    save_json(INCIDENTS_FILE, incidents)
+}
+
+// Splits off the oldest closed incidents until at most `max` remain. Open
+// incidents are always kept, so the log may still end up longer than `max`.
+fn split_archivable(incidents: Vec<Incident>, max: usize) -> (Vec<Incident>, Vec<Incident>) {
+    let mut excess = incidents.len().saturating_sub(max);
+    incidents.into_iter().partition(|incident| {
+        if excess > 0 && incident.ended.is_some() {
+            excess -= 1;
+            false
+        } else {
+            true
+        }
+    })
+}
+
+// Archive files are named after the month an incident started in, e.g.
+// incidents-2024-06.json.
+fn incident_archive_file(incident: &Incident) -> String {
+    format!("{}{}.json", INCIDENT_ARCHIVE_PREFIX, incident.started.get(..7).unwrap_or("unknown"))
+}
+
+// Takes the oldest closed incidents out of the log once it holds more than
+// MAX_INCIDENTS, for archive_incidents to write.
+fn take_archivable(incidents: &mut Vec<Incident>) -> Vec<Incident> {
+    if *MAX_INCIDENTS == 0 || incidents.len() <= *MAX_INCIDENTS {
+        return vec![];
     }
+    let (kept, archivable) = split_archivable(mem::take(incidents), *MAX_INCIDENTS);
+    *incidents = kept;
+    archivable
+}
 
-    function updateAllRelativeTimes() {
-      let timeDisplays = document.getElementsByClassName('time-display');
-      for (let td of timeDisplays) {
-        let crawlTime = td.getAttribute('data-crawl-time');
-        td.textContent = computeTimeDisplay(crawlTime);
-      }
+// Appends incidents to their monthly archive files, returning those whose
+// archive couldn't be written.
+fn archive_incidents(archivable: Vec<Incident>) -> Vec<Incident> {
+    let mut by_file: BTreeMap<String, Vec<Incident>> = BTreeMap::new();
+    for incident in archivable {
+        by_file.entry(incident_archive_file(&incident)).or_default().push(incident);
     }
-    setInterval(updateAllRelativeTimes, 1000);
+    let mut failed = vec![];
+    for (file, month) in by_file {
+        let mut archive = match load_incidents(&file) {
+            Ok(archive) => archive,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            // Never overwrite an archive that failed to parse.
+            Err(e) => {
+                error!(file = %file, error = %e, "Failed to read incident archive");
+                failed.extend(month);
+                continue;
+            }
+        };
+        let count = month.len();
+        archive.extend(month.iter().cloned());
+        match save_json(&file, &archive) {
+            Ok(()) => info!(file = %file, count, "Archived incidents"),
+            Err(e) => {
+                error!(error = %e, "Failed to archive incidents");
+                failed.extend(month);
+            }
+        }
+    }
+    failed
+}
 
-    function showAlert(message, type = 'success') {
-      const alertContainer = document.getElementById('alert-container');
-      const alertDiv = document.createElement('div');
-      alertDiv.className = `alert alert-${type} alert-dismissible fade show`;
-      alertDiv.role = 'alert';
-      alertDiv.innerHTML = `
-        ${message}
-        <button type="button" class="btn-close" data-bs-dismiss="alert" aria-label="Close"></button>
-      `;
-      alertContainer.appendChild(alertDiv);
-      setTimeout(() => {
-        const bsAlert = new bootstrap.Alert(alertDiv);
-        bsAlert.close();
-      }, 3000);
+// Archives and saves incidents on a blocking thread, so the poll task and the
+// INCIDENTS lock never wait on the disk. Incidents that failed to archive go
+// back into the log and are retried with the next incident.
+fn persist_incidents(archivable: Vec<Incident>) {
+    tokio::task::spawn_blocking(move || {
+        // Snapshot under this lock, so an older snapshot never overwrites a newer one.
+        static SAVING: Mutex<()> = Mutex::new(());
+        let _saving = SAVING.write_or_recover();
+        let failed = archive_incidents(archivable);
+        let incidents = {
+            let mut incidents = INCIDENTS.write_or_recover();
+            incidents.splice(0..0, failed);
+            incidents.clone()
+        };
+        if let Err(e) = save_incidents(&incidents) {
+            error!(error = %e, "Failed to save incidents");
+        }
+    });
+}
+
+fn open_db(path: &str) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS metrics (
+             frontend TEXT NOT NULL,
+             timestamp TEXT NOT NULL,
+             cpu REAL,
+             memory REAL,
+             disk_max REAL,
+             connectivity TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS metrics_frontend_timestamp ON metrics (frontend, timestamp);
+         CREATE TABLE IF NOT EXISTS status_history (
+             frontend TEXT NOT NULL,
+             timestamp TEXT NOT NULL,
+             status_code INTEGER NOT NULL,
+             response_ms INTEGER
+         );
+         CREATE INDEX IF NOT EXISTS status_history_frontend_timestamp ON status_history (frontend, timestamp);",
+    )?;
+    Ok(conn)
+}
+
+// The database connection, or None when DB_PATH is unset. A panic mid-insert
+// rolls its transaction back, so a poisoned lock is safe to recover.
+fn db() -> Option<MutexGuard<'static, Connection>> {
+    Some(DB.as_ref()?.write_or_recover())
+}
+
+// Inserts one metrics row per polled frontend, and a status_history row per
+// polled website, in a single transaction.
+fn store_history<'a>(conn: &mut Connection, usages: impl IntoIterator<Item = &'a ServerUsage>) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut metrics = tx.prepare_cached(
+            "INSERT INTO metrics (frontend, timestamp, cpu, memory, disk_max, connectivity) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        let mut statuses = tx.prepare_cached(
+            "INSERT INTO status_history (frontend, timestamp, status_code, response_ms) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for usage in usages {
+            let disk_max = usage
+                .disk_usage
+                .iter()
+                .flatten()
+                .filter(|d| !d.ignored)
+                .map(|d| d.used_percent)
+                .reduce(f64::max);
+            metrics.execute(params![
+                usage.frontend.name,
+                usage.crawl_time,
+                usage.cpu_usage,
+                usage.memory_usage.as_ref().map(|m| m.memory_percent),
+                disk_max,
+                usage.connectivity.as_str(),
+            ])?;
+            if let Some(record) = usage.status_history.as_ref().and_then(|history| history.last()) {
+                statuses.execute(params![usage.frontend.name, record.crawl_time, record.status_code, record.response_ms])?;
+            }
+        }
     }
+    tx.commit()
+}
 
-    function renderServers(serversData) {
-      const container = document.getElementById('servers');
-      container.innerHTML = '';
-      serversData.forEach(srv => {
-        const frontend = srv.frontend;
-        const isWebsite = frontend.type.toLowerCase() === "website";
-        const connectivity = srv.connectivity;
-        const overallStatus = srv.overall_status;
-        const serverDiv = document.createElement('div');
-        serverDiv.className = 'server-container';
+// The shared Redis connection, or None without REDIS_URL. The manager
+// reconnects by itself after a failure; if Redis was unreachable when it was
+// first needed, the next call tries again.
+async fn redis_connection() -> Option<redis::RedisResult<ConnectionManager>> {
+    let client = REDIS.as_ref()?;
+    let config = ConnectionManagerConfig::new()
+        .set_connection_timeout(REDIS_TIMEOUT)
+        .set_response_timeout(REDIS_TIMEOUT)
+        .set_number_of_retries(1);
+    let conn = REDIS_CONNECTION.get_or_try_init(|| client.get_connection_manager_with_config(config)).await;
+    Some(conn.cloned())
+}
 
-        // Header
-        const headerDiv = document.createElement('div');
-        headerDiv.className = 'server-header';
-        const infoSpan = document.createElement('span');
-        infoSpan.className = 'server-info';
-        infoSpan.innerHTML = `${frontend.name} (IP/Address: ${frontend.ip})`;
-        let timeSpan = document.createElement('span');
-        timeSpan.className = 'time-display';
-        timeSpan.setAttribute('data-crawl-time', srv.crawl_time);
-        timeSpan.style.marginLeft = "10px";
-        timeSpan.textContent = computeTimeDisplay(srv.crawl_time);
-        infoSpan.appendChild(timeSpan);
-        infoSpan.style.cursor = 'pointer';
-        headerDiv.appendChild(infoSpan);
+// Runs a command, or does nothing and returns None without REDIS_URL.
+async fn redis_query<T: FromRedisValue>(cmd: &redis::Cmd) -> Option<redis::RedisResult<T>> {
+    let conn = redis_connection().await?;
+    Some(match conn {
+        Ok(mut conn) => cmd.query_async(&mut conn).await,
+        Err(e) => Err(e),
+    })
+}
 
-        const deleteBtn = document.createElement('button');
-        deleteBtn.className = 'btn btn-sm btn-danger';
-        deleteBtn.textContent = 'Delete';
-        deleteBtn.addEventListener('click', () => {
-          if (confirm("Are you sure you want to delete this frontend?")) {
-            deleteFrontend(frontend.name);
-          }
-        });
-        headerDiv.appendChild(deleteBtn);
+// The raw value of a key, or None without REDIS_URL, for a missing key or on
+// an error, which is logged.
+async fn redis_get_raw(key: &str) -> Option<String> {
+    redis_query(redis::cmd("GET").arg(key)).await?.unwrap_or_else(|e| {
+        error!(key, error = %e, "Failed to read from Redis");
+        None
+    })
+}
 
-        const statusContainer = document.createElement('span');
-        const connectivitySpan = document.createElement('span');
-        connectivitySpan.className = `status-label ${connectivity}`;
-        connectivitySpan.innerHTML = `[Connectivity: ${connectivity === 'green' ? 'OK' : 'Down'}]`;
-        statusContainer.appendChild(connectivitySpan);
-        const overallSpan = document.createElement('span');
-        overallSpan.className = `status-label ${overallStatus}`;
-        const overallIcon = overallStatus === 'green'
-          ? '<span class="green">&#x2714;</span>'
-          : '<span class="red">&#x26A0;</span>';
-        overallSpan.innerHTML = `[Overall: ${overallIcon}]`;
-        statusContainer.appendChild(overallSpan);
-        headerDiv.appendChild(statusContainer);
-        serverDiv.appendChild(headerDiv);
+async fn redis_get<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let data = redis_get_raw(key).await?;
+    serde_json::from_str(&data)
+        .map_err(|e| error!(key, error = %e, "Failed to parse a value from Redis"))
+        .ok()
+}
 
-        // Tab group container.
-        const tabGroup = document.createElement('div');
-        tabGroup.className = 'tab-group';
-        tabGroup.style.display = (window.expandedStates[frontend.name] && window.expandedStates[frontend.name] !== "") ? 'block' : 'none';
-        infoSpan.addEventListener('click', () => {
-          if (tabGroup.style.display === 'none') {
-            tabGroup.style.display = 'block';
-            if (!window.expandedStates[frontend.name] || window.expandedStates[frontend.name] === "") {
-              window.expandedStates[frontend.name] = 'open';
-            }
-          } else {
-            tabGroup.style.display = 'none';
-            window.expandedStates[frontend.name] = '';
-          }
-        });
+// Stores a value as JSON, unless REDIS_URL is unset.
+async fn redis_set<T: Serialize + ?Sized>(key: &str, value: &T) -> redis::RedisResult<()> {
+    let data = serde_json::to_string(value).map_err(io::Error::from)?;
+    redis_query(redis::cmd("SET").arg(key).arg(data)).await.unwrap_or(Ok(()))
+}
 
-        if (isWebsite) {
-          // Website: show Status History tab.
-          const statusTabItem = document.createElement('div');
-          statusTabItem.className = 'tab-item';
-          const statusTab = document.createElement('div');
-          statusTab.className = 'tab';
-          const statusTabIcon = overallStatus === 'red'
-            ? '<span class="red">&#x26A0;</span>'
-            : '<span class="green">&#x2714;</span>';
-          statusTab.innerHTML = `Status History ${statusTabIcon}`;
-          statusTab.addEventListener('click', () => {
-            if (window.expandedStates[frontend.name] === 'status') {
-              window.expandedStates[frontend.name] = 'open';
-              statusContent.style.display = 'none';
-            } else {
-              window.expandedStates[frontend.name] = 'status';
-              statusContent.style.display = 'block';
+// Whether this backend should poll: always without REDIS_URL, otherwise only
+// while it holds the poller lock. An unreachable Redis counts as not holding
+// it, since another backend may still hold it.
+async fn hold_poller_lock() -> bool {
+    let Some(conn) = redis_connection().await else {
+        return true;
+    };
+    let held = match conn {
+        Ok(mut conn) => {
+            POLLER_LOCK_SCRIPT
+                .key(REDIS_POLLER_KEY)
+                .arg(&*INSTANCE_ID)
+                .arg(POLLER_LOCK_TTL.as_millis() as u64)
+                .invoke_async::<i32>(&mut conn)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    match held {
+        Ok(held) => held == 1,
+        Err(e) => {
+            error!(error = %e, "Failed to take the poller lock");
+            false
+        }
+    }
+}
+
+// Takes and renews the poller lock until shutdown, keeping POLLER up to date.
+async fn keep_poller_lock(mut shutdown: watch::Receiver<bool>) {
+    loop {
+        let held = hold_poller_lock().await;
+        let was_held = POLLER.swap(held, Ordering::Relaxed);
+        if held && !was_held {
+            info!("Took the poller lock, polling frontends");
+        } else if !held && was_held {
+            warn!("Lost the poller lock, following the backend that holds it");
+        }
+        tokio::select! {
+            _ = time::sleep(POLLER_LOCK_TTL / 3) => {}
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+// Whether this backend holds the poller lock right now, asked of Redis rather
+// than POLLER so that a backend that lost the lock mid-batch, e.g. while Redis
+// was briefly unreachable, can't alert alongside the backend that took it over.
+async fn owns_poller_lock() -> bool {
+    let Some(owner) = redis_query::<Option<String>>(redis::cmd("GET").arg(REDIS_POLLER_KEY)).await else {
+        return true;
+    };
+    match owner {
+        Ok(owner) => owner.as_deref() == Some(INSTANCE_ID.as_str()),
+        Err(e) => {
+            error!(error = %e, "Failed to check the poller lock");
+            false
+        }
+    }
+}
+
+async fn release_poller_lock() {
+    let Some(conn) = redis_connection().await else {
+        return;
+    };
+    let released = match conn {
+        Ok(mut conn) => POLLER_RELEASE_SCRIPT.key(REDIS_POLLER_KEY).arg(&*INSTANCE_ID).invoke_async::<i32>(&mut conn).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = released {
+        error!(error = %e, "Failed to release the poller lock");
+    }
+}
+
+// A change to one shared frontend: its new JSON, or None once it was deleted.
+// Serialized while the frontends are still locked, so a concurrent reload
+// from Redis can't slip an older copy in before the change is shared.
+type FrontendChange = (String, Option<String>);
+
+fn frontend_change(frontends: &Frontends, name: &str) -> FrontendChange {
+    let data = frontends.get(name).and_then(|fe| {
+        serde_json::to_string(fe).map_err(|e| error!(frontend = %name, error = %e, "Failed to serialize frontend")).ok()
+    });
+    (name.to_string(), data)
+}
+
+// Writes changed frontends to the shared hash, one field per frontend, so
+// backends changing different frontends at once don't undo each other. The
+// change has already been applied here, so a failure is only logged and the
+// other backends pick the change up with the next one that succeeds.
+async fn share_frontend_changes(changes: Vec<FrontendChange>) {
+    if REDIS.is_none() || changes.is_empty() {
+        return;
+    }
+    let mut pipe = redis::pipe();
+    for (name, data) in &changes {
+        match data {
+            Some(data) => pipe.hset(REDIS_FRONTENDS_KEY, name, data).ignore(),
+            None => pipe.hdel(REDIS_FRONTENDS_KEY, name).ignore(),
+        };
+    }
+    let shared = match redis_connection().await {
+        Some(Ok(mut conn)) => pipe.query_async::<()>(&mut conn).await,
+        Some(Err(e)) => Err(e),
+        None => Ok(()),
+    };
+    if let Err(e) = shared {
+        let names: Vec<&String> = changes.iter().map(|(name, _)| name).collect();
+        error!(frontends = ?names, error = %e, "Failed to share frontend changes through Redis");
+    }
+}
+
+// Seeds the shared frontends from this backend's file, unless another backend
+// already did.
+async fn seed_shared_frontends() {
+    let fields: Vec<(String, String)> = FRONTENDS
+        .read_or_recover()
+        .values()
+        .filter_map(|fe| Some((fe.name.clone(), serde_json::to_string(fe).ok()?)))
+        .collect();
+    let seeded = match redis_connection().await {
+        Some(Ok(mut conn)) => {
+            let mut invocation = SEED_FRONTENDS_SCRIPT.key(REDIS_FRONTENDS_KEY);
+            for (name, data) in &fields {
+                invocation.arg(name).arg(data);
             }
-          });
-          statusTabItem.appendChild(statusTab);
-          const statusContent = document.createElement('div');
-          statusContent.id = `status-content-${frontend.name}`;
-          statusContent.className = 'tab-content';
-          if (srv.status_history && srv.status_history.length > 0) {
-            let tableHtml = `<table class="table table-striped">
-              <thead>
-                <tr>
-                  <th>Status Code</th>
-                  <th>Crawl Time</th>
-                </tr>
-              </thead>
-              <tbody>`;
-            srv.status_history.forEach(record => {
-              const codeIcon = record.status_code == 200
-                ? '<span class="green">&#x2714;</span>'
-                : '<span class="red">&#x26A0;</span>';
-              tableHtml += `<tr>
-                <td>${record.status_code} ${codeIcon}</td>
-                <td>${record.crawl_time}</td>
-              </tr>`;
-            });
-            tableHtml += `</tbody></table>`;
-            statusContent.innerHTML = tableHtml;
-          } else {
-            statusContent.innerHTML = `<p class="text-danger">No status history available.</p>`;
-          }
-          statusContent.style.display = (window.expandedStates[frontend.name] === 'status') ? 'block' : 'none';
-          statusTabItem.appendChild(statusContent);
-          tabGroup.appendChild(statusTabItem);
-        } else {
-          // Server: show Disk, CPU, and Memory tabs.
-          const diskTabItem = document.createElement('div');
-          diskTabItem.className = 'tab-item';
-          const diskTab = document.createElement('div');
-          diskTab.className = 'tab';
-          const diskTabIcon = srv.disk_status === 'red'
-            ? '<span class="red">&#x26A0;</span>'
-            : '<span class="green">&#x2714;</span>';
-          diskTab.innerHTML = `Disk Usage ${diskTabIcon}`;
-          diskTab.addEventListener('click', () => {
-            if (window.expandedStates[frontend.name] === 'disk') {
-              window.expandedStates[frontend.name] = 'open';
-              diskContent.style.display = 'none';
-            } else {
-              window.expandedStates[frontend.name] = 'disk';
-              diskContent.style.display = 'block';
-              cpuContent.style.display = 'none';
-              memoryContent.style.display = 'none';
+            invocation.invoke_async::<i32>(&mut conn).await.map(|_| ())
+        }
+        Some(Err(e)) => Err(e),
+        None => Ok(()),
+    };
+    if let Err(e) = seeded {
+        error!(error = %e, "Failed to seed the shared frontends in Redis");
+    }
+}
+
+// Replaces the frontends with the shared ones, which any backend may have
+// changed. Frontends keep their place in the local order, and ones added
+// elsewhere go at the end, sorted by name since the hash has no order.
+async fn load_shared_frontends() {
+    let shared: HashMap<String, String> = match redis_query(redis::cmd("HGETALL").arg(REDIS_FRONTENDS_KEY)).await {
+        Some(Ok(shared)) => shared,
+        Some(Err(e)) => {
+            error!(error = %e, "Failed to read the shared frontends from Redis");
+            return;
+        }
+        None => return,
+    };
+    let shared: BTreeMap<String, FrontendInfo> = shared
+        .into_iter()
+        .filter_map(|(name, data)| match serde_json::from_str(&data) {
+            Ok(fe) => Some((name, fe)),
+            Err(e) => {
+                error!(frontend = %name, error = %e, "Failed to parse a shared frontend");
+                None
             }
-          });
-          diskTabItem.appendChild(diskTab);
-          const diskContent = document.createElement('div');
+        })
+        .collect();
+    apply_shared_frontends(shared);
+}
+
+// Swaps in the shared frontends, keeping this backend's order and appending
+// new ones, and forgets those another backend deleted.
+fn apply_shared_frontends(mut shared: BTreeMap<String, FrontendInfo>) {
+    let removed: Vec<String> = {
+        let mut frontends = FRONTENDS.write_or_recover();
+        let mut merged: Frontends = frontends.keys().filter_map(|name| shared.remove_entry(name)).collect();
+        merged.extend(shared);
+        let removed = frontends.keys().filter(|name| !merged.contains_key(*name)).cloned().collect();
+        *frontends = Arc::new(merged);
+        removed
+    };
+    for name in removed {
+        info!(frontend = %name, "Frontend deleted by another backend");
+        forget_frontend(&name);
+    }
+}
+
+// Alert state that must survive a change of poller, so the new one neither
+// repeats alerts nor ignores cooldowns. Alert times are Unix seconds.
+#[derive(Serialize, Deserialize)]
+struct SharedAlertState {
+    last_statuses: HashMap<String, HashMap<String, Status>>,
+    last_alerts: HashMap<String, u64>,
+}
+
+async fn save_shared_state(usage_data: &UsageData) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let state = SharedAlertState {
+        last_statuses: LAST_STATUSES.read_or_recover().clone(),
+        last_alerts: LAST_ALERTS
+            .read_or_recover()
+            .iter()
+            .map(|(name, at)| (name.clone(), now.saturating_sub(at.elapsed().as_secs())))
+            .collect(),
+    };
+    let usages: Vec<&ServerUsage> = usage_data.values().collect();
+    let mut saved = redis_set(REDIS_ALERT_STATE_KEY, &state).await;
+    if saved.is_ok() {
+        saved = redis_set(REDIS_USAGE_KEY, &usages).await;
+    }
+    if let Err(e) = saved {
+        error!(error = %e, "Failed to save shared state to Redis");
+    }
+}
+
+async fn load_shared_alert_state() {
+    let Some(state) = redis_get::<SharedAlertState>(REDIS_ALERT_STATE_KEY).await else {
+        return;
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    *LAST_STATUSES.write_or_recover() = state.last_statuses;
+    // Alerts too old to be represented have long left their cooldown.
+    *LAST_ALERTS.write_or_recover() = state
+        .last_alerts
+        .into_iter()
+        .filter_map(|(name, at)| Some((name, Instant::now().checked_sub(Duration::from_secs(now.saturating_sub(at)))?)))
+        .collect();
+}
+
+// Shares a pause set on this backend, so the poller follows it whichever
+// backend received it, and it outlives a change of poller.
+async fn share_alert_pause(pause: AlertPause) {
+    if REDIS.is_none() {
+        return;
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let shared = match pause {
+        AlertPause::Off => SharedAlertPause::Off,
+        AlertPause::Indefinite => SharedAlertPause::Indefinite,
+        AlertPause::Until(until) => {
+            SharedAlertPause::Until(now.saturating_add(until.saturating_duration_since(Instant::now()).as_secs()))
+        }
+    };
+    if let Err(e) = redis_set(REDIS_ALERT_PAUSE_KEY, &shared).await {
+        error!(error = %e, "Failed to share the alert pause with Redis");
+    }
+}
+
+// Follows the pause last set on any backend. Keeps the current one while the
+// key is missing or unreadable.
+async fn load_shared_alert_pause() {
+    let Some(shared) = redis_get::<SharedAlertPause>(REDIS_ALERT_PAUSE_KEY).await else {
+        return;
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let pause = match shared {
+        SharedAlertPause::Off => AlertPause::Off,
+        SharedAlertPause::Indefinite => AlertPause::Indefinite,
+        SharedAlertPause::Until(until) if until <= now => AlertPause::Off,
+        SharedAlertPause::Until(until) => {
+            Instant::now().checked_add(Duration::from_secs(until - now)).map_or(AlertPause::Indefinite, AlertPause::Until)
+        }
+    };
+    *ALERT_PAUSE.write_or_recover() = pause;
+}
+
+// Names of the monthly incident archives, oldest first.
+fn incident_archives() -> io::Result<Vec<String>> {
+    let mut files: Vec<String> = fs::read_dir(".")?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with(INCIDENT_ARCHIVE_PREFIX) && name.ends_with(".json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn load_usage_cache() -> std::io::Result<UsageData> {
+    let mut file = File::open(USAGE_CACHE_FILE)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+    let usages: Vec<ServerUsage> = serde_json::from_str(&data)?;
+    Ok(usages.into_iter().map(|usage| (usage.frontend.name.clone(), usage)).collect())
+}
+
+fn save_usage_cache(usage_data: &UsageData) -> Result<(), AppError> {
+    // The poll loop saves in the background and shutdown saves once more, so
+    // keep two writes from sharing the temp file.
+    static SAVING: Mutex<()> = Mutex::new(());
+    let _saving = SAVING.write_or_recover();
+    let usages: Vec<&ServerUsage> = usage_data.values().collect();
+    let data = serde_json::to_vec(&usages).map_err(|e| AppError::save(USAGE_CACHE_FILE, e.into()))?;
+    write_atomic(Path::new(USAGE_CACHE_FILE), &data).map_err(|e| AppError::save(USAGE_CACHE_FILE, e))
+}
+
+fn save_json<T: Serialize + ?Sized>(file: &str, value: &T) -> Result<(), AppError> {
+    let data = serde_json::to_vec_pretty(value).map_err(|e| AppError::save(file, e.into()))?;
+    write_atomic(Path::new(file), &data).map_err(|e| AppError::save(file, e))
+}
+
+// Writes to a sibling temp file and renames it into place, so a crash mid-write
+// never leaves a truncated file behind.
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+#[derive(Deserialize)]
+struct ServersQuery {
+    tag: Option<String>,    // only frontends with this tag
+    status: Option<Status>, // only frontends with this overall status
+    page: Option<usize>,    // 1-based; setting page or per_page wraps the response
+    per_page: Option<usize>,
+    sort: Option<SortOrder>, // configured order if unset
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Status, // red, then yellow, then green, each by name
+}
+
+const DEFAULT_PER_PAGE: usize = 50;
+
+// One page of /api/servers results, with the total number of matches.
+#[derive(Serialize)]
+struct ServersPage<'a> {
+    total: usize,
+    page: usize,
+    per_page: usize,
+    servers: &'a [&'a ServerUsage],
+}
+
+// The usage records selected by the query, in configured order unless the
+// query asks for another.
+fn select_servers<'a>(usage_data: &'a UsageData, query: &ServersQuery) -> Vec<&'a ServerUsage> {
+    let mut servers: Vec<&ServerUsage> = usage_data
+        .values()
+        .filter(|usage| {
+            query.tag.as_ref().is_none_or(|tag| usage.frontend.tags.contains(tag))
+                && query.status.is_none_or(|status| usage.overall_status == status)
+        })
+        .collect();
+    if let Some(SortOrder::Status) = query.sort {
+        servers.sort_by(|a, b| {
+            b.overall_status
+                .cmp(&a.overall_status)
+                .then_with(|| a.frontend.name.cmp(&b.frontend.name))
+        });
+    }
+    servers
+}
+
+// Returns a bare array unless `page` or `per_page` is given, so existing
+// clients keep working.
+#[get("/api/servers")]
+async fn api_servers(query: web::Query<ServersQuery>) -> impl Responder {
+    let usage_data = Arc::clone(&USAGE_DATA.read_or_recover());
+    let servers = select_servers(&usage_data, &query);
+    if query.page.is_none() && query.per_page.is_none() {
+        return HttpResponse::Ok().json(servers);
+    }
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE).max(1);
+    let start = (page - 1).saturating_mul(per_page).min(servers.len());
+    let end = start.saturating_add(per_page).min(servers.len());
+    HttpResponse::Ok().json(ServersPage {
+        total: servers.len(),
+        page,
+        per_page,
+        servers: &servers[start..end],
+    })
+}
+
+// Liveness probe: the server is up and handling requests.
+#[get("/health")]
+async fn health() -> impl Responder {
+    HttpResponse::Ok().body("OK")
+}
+
+// Readiness probe: fails until the first poll cycle has populated the data.
+#[get("/ready")]
+async fn ready() -> impl Responder {
+    if READY.load(Ordering::Relaxed) {
+        HttpResponse::Ok().body("Ready")
+    } else {
+        HttpResponse::ServiceUnavailable().body("Waiting for the first poll cycle")
+    }
+}
+
+// Frontend counts by overall status, for badges and external dashboards.
+#[derive(Serialize, Debug, PartialEq)]
+struct Summary {
+    total: usize,
+    green: usize,
+    yellow: usize,
+    red: usize,
+    stale: usize, // counted in addition to their status
+    worst_status: Status, // Green if there are no frontends
+}
+
+fn summarize(servers: &[&ServerUsage]) -> Summary {
+    let count = |status| servers.iter().filter(|usage| usage.overall_status == status).count();
+    Summary {
+        total: servers.len(),
+        green: count(Status::Green),
+        yellow: count(Status::Yellow),
+        red: count(Status::Red),
+        stale: servers.iter().filter(|usage| usage.stale).count(),
+        worst_status: Status::worst(servers.iter().map(|usage| usage.overall_status)),
+    }
+}
+
+#[get("/api/summary")]
+async fn api_summary(query: web::Query<ServersQuery>) -> impl Responder {
+    let usage_data = Arc::clone(&USAGE_DATA.read_or_recover());
+    HttpResponse::Ok().json(summarize(&select_servers(&usage_data, &query)))
+}
+
+#[get("/api/servers/{name}")]
+async fn api_server(path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    let usage = USAGE_DATA.read_or_recover().get(&name).cloned();
+    match usage {
+        Some(usage) => HttpResponse::Ok().json(usage),
+        None => HttpResponse::NotFound().body("Server not found"),
+    }
+}
+
+// JSON Schema of the records served by /api/servers and /api/stream, for
+// clients that validate or generate code from it.
+#[get("/api/schema")]
+async fn api_schema() -> impl Responder {
+    HttpResponse::Ok().json(schema_for!(ServerUsage))
+}
+
+#[get("/api/servers/{name}/history")]
+async fn api_server_history(path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    if !FRONTENDS.read_or_recover().contains_key(&name) {
+        return HttpResponse::NotFound().body("Server not found");
+    }
+    let history: Vec<ServerSample> = SERVER_HISTORY
+        .read_or_recover()
+        .get(&name)
+        .map(|history| history.iter().cloned().collect())
+        .unwrap_or_default();
+    HttpResponse::Ok().json(history)
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    name: String,
+    from: Option<String>, // crawl times, e.g. "2024-05-01 00:00:00", both inclusive
+    to: Option<String>,
+}
+
+// One row of the metrics table. Fields a frontend type doesn't report are None.
+#[derive(Serialize)]
+struct MetricRecord {
+    crawl_time: String,
+    cpu_usage: Option<f32>,
+    memory_percent: Option<f64>,
+    disk_max: Option<f64>,
+    connectivity: String,
+}
+
+#[derive(Serialize)]
+struct History {
+    metrics: Vec<MetricRecord>,
+    status_history: Vec<StatusRecord>,
+}
+
+// Reads the history of a frontend between two crawl times from the database,
+// newest MAX_HISTORY_ROWS rows of each table at most, oldest first.
+fn query_history(conn: &Connection, query: &HistoryQuery) -> rusqlite::Result<History> {
+    let mut metrics = conn
+        .prepare_cached(
+            "SELECT timestamp, cpu, memory, disk_max, connectivity FROM metrics
+             WHERE frontend = ?1 AND (?2 IS NULL OR timestamp >= ?2) AND (?3 IS NULL OR timestamp <= ?3) ORDER BY timestamp DESC LIMIT ?4",
+        )?
+        .query_map(params![query.name, query.from, query.to, MAX_HISTORY_ROWS], |row| {
+            Ok(MetricRecord {
+                crawl_time: row.get(0)?,
+                cpu_usage: row.get(1)?,
+                memory_percent: row.get(2)?,
+                disk_max: row.get(3)?,
+                connectivity: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut status_history = conn
+        .prepare_cached(
+            "SELECT timestamp, status_code, response_ms FROM status_history
+             WHERE frontend = ?1 AND (?2 IS NULL OR timestamp >= ?2) AND (?3 IS NULL OR timestamp <= ?3) ORDER BY timestamp DESC LIMIT ?4",
+        )?
+        .query_map(params![query.name, query.from, query.to, MAX_HISTORY_ROWS], |row| {
+            Ok(StatusRecord { crawl_time: row.get(0)?, status_code: row.get(1)?, response_ms: row.get(2)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    metrics.reverse();
+    status_history.reverse();
+    Ok(History { metrics, status_history })
+}
+
+// Without DB_PATH, the same shape built from the in-memory histories, which
+// only cover the last few polls.
+fn memory_history(query: &HistoryQuery) -> History {
+    let in_range = |crawl_time: &str| {
+        query.from.as_deref().is_none_or(|from| crawl_time >= from)
+            && query.to.as_deref().is_none_or(|to| crawl_time <= to)
+    };
+    let metrics = SERVER_HISTORY
+        .read_or_recover()
+        .get(&query.name)
+        .into_iter()
+        .flatten()
+        .filter(|sample| in_range(&sample.crawl_time))
+        .map(|sample| MetricRecord {
+            crawl_time: sample.crawl_time.clone(),
+            cpu_usage: Some(sample.cpu_usage),
+            memory_percent: Some(sample.memory_percent),
+            disk_max: sample.disk_max,
+            connectivity: Status::Green.as_str().to_string(),
+        })
+        .collect();
+    let status_history = WEBSITE_HISTORY
+        .read_or_recover()
+        .get(&query.name)
+        .into_iter()
+        .flatten()
+        .filter(|record| in_range(&record.crawl_time))
+        .cloned()
+        .collect();
+    History { metrics, status_history }
+}
+
+#[get("/api/history")]
+async fn api_history(query: web::Query<HistoryQuery>) -> impl Responder {
+    let query = query.into_inner();
+    if DB.is_none() {
+        return HttpResponse::Ok().json(memory_history(&query));
+    }
+    let history = web::block(move || db().map(|conn| query_history(&conn, &query))).await;
+    match history {
+        Ok(Some(Ok(history))) => HttpResponse::Ok().json(history),
+        Ok(Some(Err(e))) => {
+            error!(error = %e, "Failed to query history");
+            HttpResponse::InternalServerError().body("Failed to query history")
+        }
+        Ok(None) | Err(_) => HttpResponse::InternalServerError().body("Failed to query history"),
+    }
+}
+
+#[derive(Deserialize)]
+struct SeriesQuery {
+    metric: SeriesMetric,
+    bucket: Option<u64>, // bucket width in seconds, 60 if unset
+    from: Option<String>, // crawl times, as for /api/history
+    to: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum SeriesMetric {
+    Cpu,
+    Memory,
+    Disk, // the fullest disk
+}
+
+impl SeriesMetric {
+    // The metrics table column holding this metric.
+    fn column(self) -> &'static str {
+        match self {
+            SeriesMetric::Cpu => "cpu",
+            SeriesMetric::Memory => "memory",
+            SeriesMetric::Disk => "disk_max",
+        }
+    }
+
+    fn value(self, record: &MetricRecord) -> Option<f64> {
+        match self {
+            SeriesMetric::Cpu => record.cpu_usage.map(f64::from),
+            SeriesMetric::Memory => record.memory_percent,
+            SeriesMetric::Disk => record.disk_max,
+        }
+    }
+}
+
+// Aggregated samples within one bucket, which starts at `start`.
+#[derive(Debug, PartialEq, Serialize)]
+struct SeriesBucket {
+    start: String,
+    min: f64,
+    avg: f64,
+    max: f64,
+    count: u64,
+}
+
+// Groups (crawl time, value) samples into buckets of `bucket_secs` aligned
+// to the epoch, oldest first. Crawl times that don't parse are skipped.
+fn bucket_series<'a>(samples: impl IntoIterator<Item = (&'a str, f64)>, bucket_secs: i64) -> Vec<SeriesBucket> {
+    let mut buckets: BTreeMap<i64, (f64, f64, f64, u64)> = BTreeMap::new();
+    for (crawl_time, value) in samples {
+        let Ok(time) = NaiveDateTime::parse_from_str(crawl_time, CRAWL_TIME_FORMAT) else {
+            continue;
+        };
+        let secs = time.and_utc().timestamp();
+        let (min, sum, max, count) =
+            buckets.entry(secs - secs.rem_euclid(bucket_secs)).or_insert((f64::INFINITY, 0.0, f64::NEG_INFINITY, 0));
+        *min = min.min(value);
+        *sum += value;
+        *max = max.max(value);
+        *count += 1;
+    }
+    buckets
+        .into_iter()
+        .map(|(start, (min, sum, max, count))| SeriesBucket {
+            start: bucket_start(start),
+            min,
+            avg: sum / count as f64,
+            max,
+            count,
+        })
+        .collect()
+}
+
+fn bucket_start(secs: i64) -> String {
+    DateTime::from_timestamp(secs, 0).unwrap_or_default().naive_utc().format(CRAWL_TIME_FORMAT).to_string()
+}
+
+// The same aggregation done by SQLite, so months of rows never leave the
+// database.
+fn query_series(conn: &Connection, name: &str, bucket_secs: i64, query: &SeriesQuery) -> rusqlite::Result<Vec<SeriesBucket>> {
+    let sql = format!(
+        "SELECT CAST(strftime('%s', timestamp) AS INTEGER) / ?2 * ?2 AS bucket, MIN({0}), AVG({0}), MAX({0}), COUNT({0})
+         FROM metrics
+         WHERE frontend = ?1 AND {0} IS NOT NULL AND (?3 IS NULL OR timestamp >= ?3) AND (?4 IS NULL OR timestamp <= ?4)
+         GROUP BY bucket ORDER BY bucket",
+        query.metric.column()
+    );
+    let buckets = conn
+        .prepare_cached(&sql)?
+        .query_map(params![name, bucket_secs, query.from, query.to], |row| {
+            Ok(SeriesBucket {
+                start: bucket_start(row.get(0)?),
+                min: row.get(1)?,
+                avg: row.get(2)?,
+                max: row.get(3)?,
+                count: row.get(4)?,
+            })
+        })?
+        .collect();
+    buckets
+}
+
+#[get("/api/servers/{name}/series")]
+async fn api_server_series(path: web::Path<String>, query: web::Query<SeriesQuery>) -> Result<HttpResponse, AppError> {
+    let name = path.into_inner();
+    if !FRONTENDS.read_or_recover().contains_key(&name) {
+        return Err(AppError::NotFound("Server"));
+    }
+    let query = query.into_inner();
+    let bucket_secs = query.bucket.unwrap_or(60).clamp(1, i64::MAX as u64) as i64;
+    if DB.is_none() {
+        let history = memory_history(&HistoryQuery { name, from: query.from, to: query.to });
+        let samples = history.metrics.iter().filter_map(|m| Some((m.crawl_time.as_str(), query.metric.value(m)?)));
+        return Ok(HttpResponse::Ok().json(bucket_series(samples, bucket_secs)));
+    }
+    let series = web::block(move || db().map(|conn| query_series(&conn, &name, bucket_secs, &query))).await;
+    match series {
+        Ok(Some(Ok(series))) => Ok(HttpResponse::Ok().json(series)),
+        Ok(Some(Err(e))) => Err(AppError::Database(e.to_string())),
+        Ok(None) => Err(AppError::Database("no connection".to_string())),
+        Err(e) => Err(AppError::Database(e.to_string())),
+    }
+}
+
+#[get("/metrics")]
+async fn prometheus_metrics(query: web::Query<ServersQuery>) -> impl Responder {
+    let usage_data = Arc::clone(&USAGE_DATA.read_or_recover());
+    let mut cpu = String::new();
+    let mut memory = String::new();
+    let mut disk = String::new();
+    let mut up = String::new();
+    let mut status = String::new();
+    for usage in select_servers(&usage_data, &query) {
+        let name = escape_label(&usage.frontend.name);
+        if let Some(cpu_usage) = usage.cpu_usage {
+            cpu.push_str(&format!("server_cpu_usage{{name=\"{}\"}} {}\n", name, cpu_usage));
+        }
+        if let Some(memory_usage) = &usage.memory_usage {
+            memory.push_str(&format!("server_memory_percent{{name=\"{}\"}} {}\n", name, memory_usage.memory_percent));
+        }
+        for d in usage.disk_usage.iter().flatten() {
+            disk.push_str(&format!(
+                "server_disk_used_percent{{name=\"{}\",mount=\"{}\"}} {}\n",
+                name,
+                escape_label(&d.mount_point),
+                d.used_percent
+            ));
+        }
+        let is_up = if usage.connectivity == Status::Green { 1 } else { 0 };
+        up.push_str(&format!("server_up{{name=\"{}\"}} {}\n", name, is_up));
+        let level = match usage.overall_status {
+            Status::Green => 0,
+            Status::Yellow => 1,
+            Status::Red => 2,
+        };
+        status.push_str(&format!("server_status{{name=\"{}\"}} {}\n", name, level));
+    }
+    let mut body = String::new();
+    body.push_str("# TYPE server_cpu_usage gauge\n# HELP server_cpu_usage Global CPU usage percentage.\n");
+    body.push_str(&cpu);
+    body.push_str("# TYPE server_memory_percent gauge\n# HELP server_memory_percent Memory usage percentage.\n");
+    body.push_str(&memory);
+    body.push_str("# TYPE server_disk_used_percent gauge\n# HELP server_disk_used_percent Disk usage percentage per mount point.\n");
+    body.push_str(&disk);
+    body.push_str("# TYPE server_up gauge\n# HELP server_up Whether the frontend is reachable.\n");
+    body.push_str(&up);
+    body.push_str("# TYPE server_status gauge\n# HELP server_status Overall status: 0 green, 1 yellow (degraded), 2 red.\n");
+    body.push_str(&status);
+    body.push_str("# EOF\n");
+    HttpResponse::Ok()
+        .content_type("application/openmetrics-text; version=1.0.0; charset=utf-8")
+        .body(body)
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    history: bool, // export website status history instead of current status
+}
+
+// Quotes a CSV field if it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(fields: &[String]) -> String {
+    let mut row = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+    row.push_str("\r\n");
+    row
+}
+
+#[get("/api/export.csv")]
+async fn export_csv(query: web::Query<ExportQuery>) -> impl Responder {
+    let (filename, body) = if query.history {
+        let mut body = csv_row(&["name", "status_code", "crawl_time", "response_ms"].map(String::from));
+        let history = WEBSITE_HISTORY.read_or_recover();
+        let mut names: Vec<&String> = history.keys().collect();
+        names.sort();
+        for name in names {
+            for record in &history[name] {
+                body.push_str(&csv_row(&[
+                    name.clone(),
+                    record.status_code.to_string(),
+                    record.crawl_time.clone(),
+                    record.response_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+                ]));
+            }
+        }
+        ("website_history.csv", body)
+    } else {
+        let mut body = csv_row(
+            &[
+                "name", "ip", "type", "connectivity", "overall_status", "cpu_usage", "memory_percent", "memory_used",
+                "memory_total", "crawl_time",
+            ]
+            .map(String::from),
+        );
+        let usage_data = Arc::clone(&USAGE_DATA.read_or_recover());
+        for usage in usage_data.values() {
+            body.push_str(&csv_row(&[
+                usage.frontend.name.clone(),
+                usage.frontend.ip.clone(),
+                usage.frontend.frontend_type.clone(),
+                usage.connectivity.as_str().to_string(),
+                usage.overall_status.as_str().to_string(),
+                usage.cpu_usage.map(|c| c.to_string()).unwrap_or_default(),
+                usage.memory_usage.as_ref().map(|m| m.memory_percent.to_string()).unwrap_or_default(),
+                usage.memory_usage.as_ref().map(|m| m.used_human.clone()).unwrap_or_default(),
+                usage.memory_usage.as_ref().map(|m| m.total_human.clone()).unwrap_or_default(),
+                usage.crawl_time.clone(),
+            ]));
+        }
+        ("servers.csv", body)
+    };
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)))
+        .body(body)
+}
+
+// Sends the current usage data as a server-sent event, then again after every
+// poll cycle that polled something, with a keepalive comment whenever nothing
+// was sent for STREAM_KEEPALIVE.
+#[get("/api/stream")]
+async fn api_stream(query: web::Query<ServersQuery>) -> impl Responder {
+    let query = query.into_inner();
+    let updates = USAGE_UPDATES.subscribe();
+    let current = Arc::clone(&USAGE_DATA.read_or_recover());
+    let snapshots = stream::once(future::ready(Some(current))).chain(stream::unfold(updates, |mut updates| async move {
+        loop {
+            tokio::select! {
+                update = updates.recv() => match update {
+                    Ok(usage_data) => return Some((Some(usage_data), updates)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                _ = time::sleep(STREAM_KEEPALIVE) => return Some((None, updates)),
+            }
+        }
+    }));
+    let events = snapshots.map(move |usage_data| {
+        let Some(usage_data) = usage_data else {
+            return Ok(web::Bytes::from_static(b": keepalive\n\n"));
+        };
+        let servers = select_servers(&usage_data, &query);
+        let data = serde_json::to_string(&servers)?;
+        Ok::<_, actix_web::Error>(web::Bytes::from(format!("data: {}\n\n", data)))
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+#[get("/api/incidents")]
+async fn api_incidents() -> impl Responder {
+    let incidents = INCIDENTS.read_or_recover();
+    HttpResponse::Ok().json(&*incidents)
+}
+
+#[get("/api/incidents/archive")]
+async fn api_incident_archives() -> impl Responder {
+    match incident_archives() {
+        Ok(files) => HttpResponse::Ok().json(files),
+        Err(e) => {
+            error!(error = %e, "Failed to list incident archives");
+            HttpResponse::InternalServerError().body("Failed to list incident archives")
+        }
+    }
+}
+
+#[get("/api/frontends")]
+async fn api_frontends() -> impl Responder {
+    let frontends = FRONTENDS.read_or_recover();
+    let frontends: Vec<FrontendInfo> = frontends.values().map(FrontendInfo::redacted).collect();
+    HttpResponse::Ok().json(frontends)
+}
+
+// A dot in the color of the worst overall status. The dashboard refetches it
+// whenever that changes, so the browser tab shows trouble at a glance.
+#[get("/favicon.ico")]
+async fn favicon() -> impl Responder {
+    let usage_data = Arc::clone(&USAGE_DATA.read_or_recover());
+    let color = match Status::worst(usage_data.values().map(|usage| usage.overall_status)) {
+        Status::Green => "#198754",
+        Status::Yellow => "#fd7e14",
+        Status::Red => "#dc3545",
+    };
+    HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .body(format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 16 16"><circle cx="8" cy="8" r="7" fill="{}"/></svg>"#,
+            color
+        ))
+}
+
+#[get("/")]
+async fn index() -> impl Responder {
+    // The dashboard refresh rate is injected so it matches the poll loop.
+    let html = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="UTF-8">
+  <title>Monitoring Dashboard</title>
+  <link id="favicon" rel="icon" type="image/svg+xml" href="./favicon.ico">
+  <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0/dist/css/bootstrap.min.css" rel="stylesheet">
+  <style>
+    body { padding: 20px; }
+    .server-container { border: 1px solid #dee2e6; border-radius: 0.25rem; padding: 15px; margin-bottom: 15px; }
+    .server-header { display: flex; justify-content: space-between; align-items: center; }
+    .status-label { margin-left: 10px; font-weight: bold; }
+    .green { color: green; }
+    .red { color: red; }
+    .yellow, .text-yellow { color: orange; }
+    .tab-group { margin-top: 10px; }
+    .tab-item { margin-bottom: 10px; }
+    .tab { cursor: pointer; padding: 5px 10px; border: 1px solid #dee2e6; border-radius: 0.25rem; background-color: #f8f9fa; margin-right: 5px; }
+    .tab:hover { background-color: #e9ecef; }
+    .tab-content { margin-top: 5px; display: none; }
+  </style>
+</head>
+<body>
+  <div class="container">
+    <h1 class="mb-4">Monitoring Dashboard</h1>
+    <div id="alert-container"></div>
+    <div class="d-flex gap-2 mb-3">
+      <button id="addFrontendBtn" class="btn btn-primary">Add New Frontend</button>
+      <button id="importFrontendsBtn" class="btn btn-outline-primary">Import</button>
+      <input type="file" id="importFile" accept=".json,.csv" hidden>
+      <button id="pauseAlertsBtn" class="btn btn-outline-warning">Pause Alerts</button>
+      <input type="text" class="form-control w-auto" id="tagFilter" placeholder="Filter by tag">
+    </div>
+    <div id="servers"></div>
+  </div>
+
+  <!-- Add Frontend Modal -->
+  <div class="modal fade" id="addFrontendModal" tabindex="-1" aria-labelledby="addFrontendModalLabel" aria-hidden="true">
+    <div class="modal-dialog">
+      <div class="modal-content">
+        <form id="add-frontend-form">
+          <div class="modal-header">
+            <h5 class="modal-title" id="addFrontendModalLabel">Add New Frontend</h5>
+            <button type="button" class="btn-close" data-bs-dismiss="modal" aria-label="Close"></button>
+          </div>
+          <div class="modal-body">
+            <div class="mb-3">
+              <label for="frontendName" class="form-label">Server Name</label>
+              <input type="text" class="form-control" id="frontendName" name="name" required>
+            </div>
+            <div class="mb-3">
+              <label for="frontendIP" class="form-label">IP/Address</label>
+              <input type="text" class="form-control" id="frontendIP" name="ip" required>
+            </div>
+            <div class="mb-3">
+              <label for="frontendType" class="form-label">Type</label>
+              <select class="form-select" id="frontendType" name="type" required>
+                <option value="server">Server</option>
+                <option value="website">Website</option>
+                <option value="port">TCP Port</option>
+                <option value="ping">ICMP Ping</option>
+                <option value="dns">DNS Resolution</option>
+                <option value="service">Systemd Service</option>
+              </select>
+            </div>
+            <div class="mb-3">
+              <label for="frontendTags" class="form-label">Tags</label>
+              <input type="text" class="form-control" id="frontendTags" name="tags" placeholder="prod, web">
+            </div>
+          </div>
+          <div class="modal-footer">
+            <button type="button" class="btn btn-secondary" data-bs-dismiss="modal">Cancel</button>
+            <button type="submit" class="btn btn-primary">Add Frontend</button>
+          </div>
+        </form>
+      </div>
+    </div>
+  </div>
+
+  <script src="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0/dist/js/bootstrap.bundle.min.js"></script>
+  <script>
+    // Global object for expanded states.
+    window.expandedStates = {};
+
+    function computeTimeDisplay(crawlTimeString) {
+      let crawlTimeISO = crawlTimeString.replace(" ", "T") + "__TZ_OFFSET__";
+      let crawlTime = new Date(crawlTimeISO);
+      let now = new Date();
+      let diffSeconds = Math.floor((now - crawlTime) / 1000);
+      return diffSeconds === 0 ? "(Just now)" : `(${diffSeconds} seconds ago)`;
+    }
+
+    function updateAllRelativeTimes() {
+      let timeDisplays = document.getElementsByClassName('time-display');
+      for (let td of timeDisplays) {
+        let crawlTime = td.getAttribute('data-crawl-time');
+        td.textContent = computeTimeDisplay(crawlTime);
+      }
+    }
+    setInterval(updateAllRelativeTimes, 1000);
+
+    // Mirrors the backend's expected_status check: a single code or a
+    // comma-separated list of codes and ranges, 200 if unset.
+    function isExpectedStatus(code, expected) {
+      return String(expected ?? 200).split(',').some(part => {
+        const [start, end = start] = part.split('-').map(Number);
+        return code >= start && code <= end;
+      });
+    }
+
+    function showAlert(message, type = 'success') {
+      const alertContainer = document.getElementById('alert-container');
+      const alertDiv = document.createElement('div');
+      alertDiv.className = `alert alert-${type} alert-dismissible fade show`;
+      alertDiv.role = 'alert';
+      alertDiv.innerHTML = `
+        ${message}
+        <button type="button" class="btn-close" data-bs-dismiss="alert" aria-label="Close"></button>
+      `;
+      alertContainer.appendChild(alertDiv);
+      setTimeout(() => {
+        const bsAlert = new bootstrap.Alert(alertDiv);
+        bsAlert.close();
+      }, 3000);
+    }
+
+    function renderServers(serversData) {
+      const container = document.getElementById('servers');
+      container.innerHTML = '';
+      serversData.forEach(srv => {
+        const frontend = srv.frontend;
+        const isWebsite = frontend.type.toLowerCase() === "website";
+        const connectivity = srv.connectivity;
+        const overallStatus = srv.overall_status;
+        const serverDiv = document.createElement('div');
+        serverDiv.className = 'server-container';
+
+        // Header
+        const headerDiv = document.createElement('div');
+        headerDiv.className = 'server-header';
+        const infoSpan = document.createElement('span');
+        infoSpan.className = 'server-info';
+        infoSpan.innerHTML = `${frontend.name} (IP/Address: ${frontend.ip})`;
+        (frontend.tags || []).forEach(tag => {
+          const tagSpan = document.createElement('span');
+          tagSpan.className = 'badge bg-secondary ms-1';
+          tagSpan.textContent = tag;
+          infoSpan.appendChild(tagSpan);
+        });
+        if (srv.flapping) {
+          const flapSpan = document.createElement('span');
+          flapSpan.className = 'badge bg-warning text-dark ms-1';
+          flapSpan.title = 'Status is changing too often; alerts are paused until it settles';
+          flapSpan.textContent = 'Flapping';
+          infoSpan.appendChild(flapSpan);
+        }
+        if (srv.stale) {
+          const staleSpan = document.createElement('span');
+          staleSpan.className = 'badge bg-danger ms-1';
+          staleSpan.title = `Not polled since ${srv.crawl_time}; the poll loop may be stuck`;
+          staleSpan.textContent = 'Stale';
+          infoSpan.appendChild(staleSpan);
+        }
+        if (srv.host != null && srv.host.hostname) {
+          const hostSpan = document.createElement('span');
+          hostSpan.className = 'host-info';
+          hostSpan.style.marginLeft = "10px";
+          hostSpan.textContent = `${srv.host.hostname} - ${srv.host.os_name} ${srv.host.os_version} (kernel ${srv.host.kernel_version})`
+            + (srv.agent_version != null ? ` - agent ${srv.agent_version}` : '')
+            + (srv.users != null ? ` - ${srv.users.length} user${srv.users.length === 1 ? '' : 's'}` : '');
+          if (srv.users != null && srv.users.length > 0) {
+            hostSpan.title = `Logged in: ${srv.users.join(', ')}`;
+          }
+          infoSpan.appendChild(hostSpan);
+        }
+        let timeSpan = document.createElement('span');
+        timeSpan.className = 'time-display';
+        timeSpan.setAttribute('data-crawl-time', srv.crawl_time);
+        timeSpan.style.marginLeft = "10px";
+        timeSpan.textContent = computeTimeDisplay(srv.crawl_time);
+        infoSpan.appendChild(timeSpan);
+        infoSpan.style.cursor = 'pointer';
+        headerDiv.appendChild(infoSpan);
+
+        const deleteBtn = document.createElement('button');
+        deleteBtn.className = 'btn btn-sm btn-danger';
+        deleteBtn.textContent = 'Delete';
+        deleteBtn.addEventListener('click', () => {
+          if (confirm("Are you sure you want to delete this frontend?")) {
+            deleteFrontend(frontend.name);
+          }
+        });
+        headerDiv.appendChild(deleteBtn);
+
+        const muteBtn = document.createElement('button');
+        muteBtn.className = 'btn btn-sm btn-secondary ms-1';
+        muteBtn.textContent = frontend.muted ? 'Unmute' : 'Mute';
+        muteBtn.title = frontend.muted ? 'Alerts are muted' : 'Mute alerts for this frontend';
+        muteBtn.addEventListener('click', () => setMuted(frontend.name, !frontend.muted));
+        headerDiv.appendChild(muteBtn);
+
+        const statusContainer = document.createElement('span');
+        const connectivitySpan = document.createElement('span');
+        connectivitySpan.className = `status-label ${connectivity}`;
+        connectivitySpan.innerHTML = `[Connectivity: ${connectivity === 'green' ? 'OK' : 'Down'}]`;
+        statusContainer.appendChild(connectivitySpan);
+        const uptimeSpan = document.createElement('span');
+        uptimeSpan.className = 'status-label';
+        uptimeSpan.textContent = `[Uptime: ${srv.uptime_percent.toFixed(2)}%]`;
+        statusContainer.appendChild(uptimeSpan);
+        if (srv.error_kind != null) {
+          const errorLabels = {
+            bad_address: 'Invalid agent address',
+            unreachable: 'Agent unreachable',
+            bad_status: 'Agent returned an error',
+            parse_error: 'Agent sent invalid metrics',
+            no_metrics: 'Agent sent no metrics'
+          };
+          const errorSpan = document.createElement('span');
+          errorSpan.className = `status-label ${srv.error_kind === 'no_metrics' ? 'yellow' : 'red'}`;
+          errorSpan.textContent = `[${errorLabels[srv.error_kind] || srv.error_kind}]`;
+          statusContainer.appendChild(errorSpan);
+        }
+        if (srv.ping_ms != null) {
+          const pingSpan = document.createElement('span');
+          pingSpan.className = 'status-label';
+          pingSpan.textContent = `[Ping: ${srv.ping_ms.toFixed(1)} ms]`;
+          statusContainer.appendChild(pingSpan);
+        }
+        if (srv.resolved_ips != null) {
+          const dnsSpan = document.createElement('span');
+          dnsSpan.className = 'status-label';
+          dnsSpan.textContent = srv.resolved_ips.length > 0
+            ? `[Resolves to: ${srv.resolved_ips.join(', ')}]`
+            : '[Does not resolve]';
+          statusContainer.appendChild(dnsSpan);
+        }
+        if (srv.service_state != null) {
+          const serviceSpan = document.createElement('span');
+          serviceSpan.className = `status-label ${srv.service_state === 'active' ? '' : 'red'}`;
+          serviceSpan.textContent = `[Service: ${srv.service_state || 'unknown'}]`;
+          statusContainer.appendChild(serviceSpan);
+        }
+        const overallSpan = document.createElement('span');
+        overallSpan.className = `status-label ${overallStatus}`;
+        const overallIcon = overallStatus === 'green'
+          ? '<span class="green">&#x2714;</span>'
+          : overallStatus === 'yellow'
+            ? '<span class="yellow">&#x26A0; degraded</span>'
+            : '<span class="red">&#x26A0;</span>';
+        overallSpan.innerHTML = `[Overall: ${overallIcon}]`;
+        statusContainer.appendChild(overallSpan);
+        headerDiv.appendChild(statusContainer);
+        serverDiv.appendChild(headerDiv);
+
+        // Tab group container.
+        const tabGroup = document.createElement('div');
+        tabGroup.className = 'tab-group';
+        tabGroup.style.display = (window.expandedStates[frontend.name] && window.expandedStates[frontend.name] !== "") ? 'block' : 'none';
+        infoSpan.addEventListener('click', () => {
+          if (tabGroup.style.display === 'none') {
+            tabGroup.style.display = 'block';
+            if (!window.expandedStates[frontend.name] || window.expandedStates[frontend.name] === "") {
+              window.expandedStates[frontend.name] = 'open';
+            }
+          } else {
+            tabGroup.style.display = 'none';
+            window.expandedStates[frontend.name] = '';
+          }
+        });
+
+        if (isWebsite) {
+          // Website: show Status History tab.
+          const statusTabItem = document.createElement('div');
+          statusTabItem.className = 'tab-item';
+          const statusTab = document.createElement('div');
+          statusTab.className = 'tab';
+          const statusTabIcon = overallStatus === 'red'
+            ? '<span class="red">&#x26A0;</span>'
+            : '<span class="green">&#x2714;</span>';
+          statusTab.innerHTML = `Status History ${statusTabIcon}`;
+          statusTab.addEventListener('click', () => {
+            if (window.expandedStates[frontend.name] === 'status') {
+              window.expandedStates[frontend.name] = 'open';
+              statusContent.style.display = 'none';
+            } else {
+              window.expandedStates[frontend.name] = 'status';
+              statusContent.style.display = 'block';
+            }
+          });
+          statusTabItem.appendChild(statusTab);
+          const statusContent = document.createElement('div');
+          statusContent.id = `status-content-${frontend.name}`;
+          statusContent.className = 'tab-content';
+          let certHtml = '';
+          if (srv.cert_days_remaining != null) {
+            certHtml = `<p>TLS certificate expires in ${srv.cert_days_remaining} days</p>`;
+          }
+          if (srv.status_history && srv.status_history.length > 0) {
+            let tableHtml = certHtml + `<table class="table table-striped">
+              <thead>
+                <tr>
+                  <th>Status Code</th>
+                  <th>Response Time (ms)</th>
+                  <th>Crawl Time</th>
+                </tr>
+              </thead>
+              <tbody>`;
+            srv.status_history.forEach(record => {
+              const codeIcon = isExpectedStatus(record.status_code, frontend.expected_status)
+                ? '<span class="green">&#x2714;</span>'
+                : '<span class="red">&#x26A0;</span>';
+              tableHtml += `<tr>
+                <td>${record.status_code} ${codeIcon}</td>
+                <td>${record.response_ms != null ? record.response_ms : '-'}</td>
+                <td>${record.crawl_time}</td>
+              </tr>`;
+            });
+            tableHtml += `</tbody></table>`;
+            statusContent.innerHTML = tableHtml;
+          } else {
+            statusContent.innerHTML = certHtml + `<p class="text-danger">No status history available.</p>`;
+          }
+          statusContent.style.display = (window.expandedStates[frontend.name] === 'status') ? 'block' : 'none';
+          statusTabItem.appendChild(statusContent);
+          tabGroup.appendChild(statusTabItem);
+        } else if (frontend.type.toLowerCase() === "server") {
+          // Server: show Disk, CPU, and Memory tabs.
+          const diskTabItem = document.createElement('div');
+          diskTabItem.className = 'tab-item';
+          const diskTab = document.createElement('div');
+          diskTab.className = 'tab';
+          const diskTabIcon = srv.disk_status === 'red' || srv.inode_status === 'red'
+            ? '<span class="red">&#x26A0;</span>'
+            : '<span class="green">&#x2714;</span>';
+          diskTab.innerHTML = `Disk Usage ${diskTabIcon}`;
+          diskTab.addEventListener('click', () => {
+            if (window.expandedStates[frontend.name] === 'disk') {
+              window.expandedStates[frontend.name] = 'open';
+              diskContent.style.display = 'none';
+            } else {
+              window.expandedStates[frontend.name] = 'disk';
+              diskContent.style.display = 'block';
+              cpuContent.style.display = 'none';
+              memoryContent.style.display = 'none';
+            }
+          });
+          diskTabItem.appendChild(diskTab);
+          const diskContent = document.createElement('div');
           diskContent.id = `disk-content-${frontend.name}`;
           diskContent.className = 'tab-content';
+          let diskIoHtml = '';
+          if (srv.disk_io != null) {
+            diskIoHtml = `<p>Disk I/O: read ${srv.disk_io.read_bytes_per_sec.toFixed(0)} B/s, write ${srv.disk_io.write_bytes_per_sec.toFixed(0)} B/s</p>`;
+          }
           if (srv.disk_usage) {
-            let tableHtml = `<table class="table table-striped">
+            let tableHtml = diskIoHtml + `<table class="table table-striped">
               <thead>
                 <tr>
                   <th>Mount Point</th>
+                  <th>Filesystem</th>
                   <th>Total (bytes)</th>
                   <th>Used (bytes)</th>
                   <th>Usage %</th>
+                  <th>Inodes %</th>
                   <th>Status</th>
                 </tr>
               </thead>
@@ -404,10 +2602,12 @@ async fn index() -> impl Responder {
             srv.disk_usage.forEach(disk => {
               tableHtml += `<tr>
                 <td>${disk.mount_point}</td>
-                <td>${disk.total}</td>
-                <td>${disk.used}</td>
+                <td>${disk.filesystem}</td>
+                <td>${disk.total_human}</td>
+                <td>${disk.used_human}</td>
                 <td>${disk.used_percent.toFixed(2)}%</td>
-                <td><span class="text-${disk.status}">${disk.status == "red" ? "&#x26A0;" : "&#x2714;"}</span></td>
+                <td>${disk.inodes_percent != null ? `<span class="text-${disk.inode_status}">${disk.inodes_percent.toFixed(2)}%</span>` : 'n/a'}</td>
+                <td><span class="text-${disk.status}">${disk.status == "red" ? "&#x26A0;" : "&#x2714;"}</span>${disk.ignored ? ' <span class="text-muted">(ignored)</span>' : ''}</td>
               </tr>`;
             });
             tableHtml += `</tbody></table>`;
@@ -415,452 +2615,2914 @@ async fn index() -> impl Responder {
           } else {
             diskContent.innerHTML = `<p class="text-danger">Unable to retrieve disk usage data.</p>`;
           }
-          diskContent.style.display = (window.expandedStates[frontend.name] === 'disk') ? 'block' : 'none';
-          diskTabItem.appendChild(diskContent);
-          tabGroup.appendChild(diskTabItem);
+          diskContent.style.display = (window.expandedStates[frontend.name] === 'disk') ? 'block' : 'none';
+          diskTabItem.appendChild(diskContent);
+          tabGroup.appendChild(diskTabItem);
+          
+          const cpuTabItem = document.createElement('div');
+          cpuTabItem.className = 'tab-item';
+          const cpuTab = document.createElement('div');
+          cpuTab.className = 'tab';
+          const cpuTabIcon = srv.cpu_status === 'red' || srv.temp_status === 'red' || srv.battery_status === 'red' || srv.gpu_status === 'red'
+            ? '<span class="red">&#x26A0;</span>'
+            : '<span class="green">&#x2714;</span>';
+          cpuTab.innerHTML = `CPU Usage ${cpuTabIcon}`;
+          cpuTab.addEventListener('click', () => {
+            if (window.expandedStates[frontend.name] === 'cpu') {
+              window.expandedStates[frontend.name] = 'open';
+              cpuContent.style.display = 'none';
+            } else {
+              window.expandedStates[frontend.name] = 'cpu';
+              cpuContent.style.display = 'block';
+              diskContent.style.display = 'none';
+              memoryContent.style.display = 'none';
+              loadSparkline(frontend.name, cpuContent.querySelector('.sparkline'));
+            }
+          });
+          cpuTabItem.appendChild(cpuTab);
+          const cpuContent = document.createElement('div');
+          cpuContent.id = `cpu-content-${frontend.name}`;
+          cpuContent.className = 'tab-content';
+          let cpuHtml = "";
+          if (srv.cpu_usage != null) {
+            cpuHtml += `<p>Global CPU Usage: ${srv.cpu_usage.toFixed(2)}%</p>`;
+            cpuHtml += `<div class="sparkline"></div>`;
+          }
+          if (srv.load_avg != null) {
+            cpuHtml += `<p>Load Average: ${srv.load_avg.one.toFixed(2)}, ${srv.load_avg.five.toFixed(2)}, ${srv.load_avg.fifteen.toFixed(2)}</p>`;
+          }
+          if (srv.cpus != null && srv.cpus.length > 0) {
+            cpuHtml += `<table class="table table-striped">
+              <thead>
+                <tr>
+                  <th>CPU Core</th>
+                  <th>Usage (%)</th>
+                  <th>Frequency (MHz)</th>
+                  <th>Status</th>
+                </tr>
+              </thead>
+              <tbody>`;
+            srv.cpus.forEach(cpu => {
+              cpuHtml += `<tr>
+                <td>${cpu.name}</td>
+                <td>${cpu.cpu_usage.toFixed(2)}</td>
+                <td>${cpu.frequency}</td>
+                <td><span class="text-${cpu.status}">${cpu.status == "red" ? "&#x26A0;" : "&#x2714;"}</span></td>
+              </tr>`;
+            });
+            cpuHtml += `</tbody></table>`;
+          } else {
+            cpuHtml += `<p class="text-danger">Unable to retrieve CPU usage data.</p>`;
+          }
+          if (srv.temperatures != null && srv.temperatures.length > 0) {
+            cpuHtml += `<p>Temperatures: ${srv.temperatures.map(t => `${t.label} ${t.celsius.toFixed(1)}&deg;C`).join(', ')} <span class="text-${srv.temp_status}">${srv.temp_status == "red" ? "&#x26A0;" : "&#x2714;"}</span></p>`;
+          }
+          if (srv.battery != null) {
+            cpuHtml += `<p>Battery: ${srv.battery.percent.toFixed(0)}% ${srv.battery.on_ac ? '(on AC)' : '(on battery)'} <span class="text-${srv.battery_status}">${srv.battery_status == "red" ? "&#x26A0;" : "&#x2714;"}</span></p>`;
+          }
+          if (srv.gpus != null && srv.gpus.length > 0) {
+            cpuHtml += `<p>GPUs: ${srv.gpus.map((g, i) => `GPU ${i} ${g.utilization_percent.toFixed(0)}% (${(g.memory_used / 1048576).toFixed(0)} / ${(g.memory_total / 1048576).toFixed(0)} MiB)`).join(', ')} <span class="text-${srv.gpu_status}">${srv.gpu_status == "red" ? "&#x26A0;" : "&#x2714;"}</span></p>`;
+          }
+          cpuContent.innerHTML = cpuHtml;
+          cpuContent.style.display = (window.expandedStates[frontend.name] === 'cpu') ? 'block' : 'none';
+          if (window.expandedStates[frontend.name] === 'cpu') {
+            loadSparkline(frontend.name, cpuContent.querySelector('.sparkline'));
+          }
+          cpuTabItem.appendChild(cpuContent);
+          tabGroup.appendChild(cpuTabItem);
           
-          const cpuTabItem = document.createElement('div');
-          cpuTabItem.className = 'tab-item';
-          const cpuTab = document.createElement('div');
-          cpuTab.className = 'tab';
-          const cpuTabIcon = srv.cpu_status === 'red'
+          const memoryTabItem = document.createElement('div');
+          memoryTabItem.className = 'tab-item';
+          const memoryTab = document.createElement('div');
+          memoryTab.className = 'tab';
+          const memoryTabIcon = srv.memory_status === 'red' || srv.swap_status === 'red'
             ? '<span class="red">&#x26A0;</span>'
             : '<span class="green">&#x2714;</span>';
-          cpuTab.innerHTML = `CPU Usage ${cpuTabIcon}`;
-          cpuTab.addEventListener('click', () => {
-            if (window.expandedStates[frontend.name] === 'cpu') {
+          memoryTab.innerHTML = `Memory Usage ${memoryTabIcon}`;
+          memoryTab.addEventListener('click', () => {
+            if (window.expandedStates[frontend.name] === 'memory') {
               window.expandedStates[frontend.name] = 'open';
-              cpuContent.style.display = 'none';
+              memoryContent.style.display = 'none';
             } else {
-              window.expandedStates[frontend.name] = 'cpu';
-              cpuContent.style.display = 'block';
+              window.expandedStates[frontend.name] = 'memory';
+              memoryContent.style.display = 'block';
               diskContent.style.display = 'none';
-              memoryContent.style.display = 'none';
+              cpuContent.style.display = 'none';
+            }
+          });
+          memoryTabItem.appendChild(memoryTab);
+          const memoryContent = document.createElement('div');
+          memoryContent.id = `memory-content-${frontend.name}`;
+          memoryContent.className = 'tab-content';
+          let memoryHtml = "";
+          if (srv.memory_usage != null) {
+            memoryHtml += `<p>Total Memory: ${srv.memory_usage.total_human}</p>`;
+            memoryHtml += `<p>Used Memory: ${srv.memory_usage.used_human}</p>`;
+            memoryHtml += `<p>Usage: ${srv.memory_usage.memory_percent.toFixed(2)}%</p>`;
+          } else {
+            memoryHtml += `<p class="text-danger">Unable to retrieve memory usage data.</p>`;
+          }
+          if (srv.swap_usage != null) {
+            memoryHtml += `<p>Total Swap: ${srv.swap_usage.total_human}</p>`;
+            memoryHtml += `<p>Used Swap: ${srv.swap_usage.used_human}</p>`;
+            memoryHtml += `<p>Swap Usage: ${srv.swap_usage.swap_percent.toFixed(2)}% <span class="text-${srv.swap_usage.status}">${srv.swap_usage.status == "red" ? "&#x26A0;" : "&#x2714;"}</span></p>`;
+          }
+          memoryContent.innerHTML = memoryHtml;
+          memoryContent.style.display = (window.expandedStates[frontend.name] === 'memory') ? 'block' : 'none';
+          memoryTabItem.appendChild(memoryContent);
+          tabGroup.appendChild(memoryTabItem);
+        }
+        
+        serverDiv.appendChild(tabGroup);
+        container.appendChild(serverDiv);
+      });
+    }
+
+    // Draws recent CPU (blue) and memory (orange) usage as an inline SVG.
+    // Only fetched while the CPU tab is open, to avoid a request per card.
+    async function loadSparkline(name, el) {
+      if (!el) return;
+      try {
+        const res = await fetch('./api/servers/' + encodeURIComponent(name) + '/history');
+        if (!res.ok) return;
+        const samples = await res.json();
+        if (samples.length < 2) return;
+        const width = 200, height = 40;
+        const points = key => samples.map((s, i) =>
+          `${(i * width / (samples.length - 1)).toFixed(1)},${(height - Math.min(s[key], 100) * height / 100).toFixed(1)}`
+        ).join(' ');
+        el.innerHTML = `<svg width="${width}" height="${height}" style="border: 1px solid #dee2e6;">
+          <polyline fill="none" stroke="steelblue" stroke-width="1.5" points="${points('cpu_usage')}"/>
+          <polyline fill="none" stroke="orange" stroke-width="1.5" points="${points('memory_percent')}"/>
+        </svg>`;
+      } catch (err) {
+        console.error('Error fetching server history:', err);
+      }
+    }
+
+    // Query string shared by polling and the event stream: unhealthy frontends
+    // first, optionally limited to one tag.
+    function serversQuery() {
+      const params = new URLSearchParams({ sort: 'status' });
+      const tag = document.getElementById('tagFilter').value.trim();
+      if (tag) {
+        params.set('tag', tag);
+      }
+      return '?' + params.toString();
+    }
+
+    async function refreshData() {
+      try {
+        const res = await fetch('./api/servers' + serversQuery());
+        const data = await res.json();
+        renderServers(data);
+      } catch (err) {
+        console.error('Error fetching server data:', err);
+      }
+    }
+
+    // Headers for add/delete requests, including the API key if one was entered.
+    function mutationHeaders() {
+      const headers = { 'Content-Type': 'application/x-www-form-urlencoded' };
+      const apiKey = localStorage.getItem('apiKey');
+      if (apiKey) {
+        headers['X-API-Key'] = apiKey;
+      }
+      return headers;
+    }
+
+    function promptForApiKey(res) {
+      if (res.status === 401) {
+        const apiKey = prompt('This action requires an API key:');
+        if (apiKey) {
+          localStorage.setItem('apiKey', apiKey);
+        }
+      }
+    }
+
+    async function addFrontend(event) {
+      event.preventDefault();
+      const formData = new FormData(document.getElementById('add-frontend-form'));
+      try {
+        const res = await fetch('./add_frontend', {
+          method: 'POST',
+          headers: mutationHeaders(),
+          body: new URLSearchParams({
+            name: formData.get('name'),
+            ip: formData.get('ip'),
+            type: formData.get('type'),
+            tags: formData.get('tags')
+          })
+        });
+        if (res.ok) {
+          document.getElementById('add-frontend-form').reset();
+          const modalEl = document.getElementById('addFrontendModal');
+          const modal = bootstrap.Modal.getInstance(modalEl);
+          modal.hide();
+          showAlert('Frontend added successfully!', 'success');
+          refreshData();
+        } else {
+          promptForApiKey(res);
+          showAlert('Error adding frontend: ' + await res.text(), 'danger');
+        }
+      } catch (err) {
+        showAlert('Error adding frontend: ' + err, 'danger');
+      }
+    }
+
+    // Uploads a .json or .csv file of frontends and reports what was skipped.
+    async function importFrontends(event) {
+      const file = event.target.files[0];
+      event.target.value = '';
+      if (!file) return;
+      const headers = mutationHeaders();
+      headers['Content-Type'] = file.name.toLowerCase().endsWith('.csv') ? 'text/csv' : 'application/json';
+      try {
+        const res = await fetch('./import_frontends', {
+          method: 'POST',
+          headers,
+          body: await file.text()
+        });
+        if (res.ok) {
+          const summary = await res.json();
+          let message = `Imported ${summary.added.length} frontend(s).`;
+          if (summary.rejected.length > 0) {
+            message += ' Skipped: ' + summary.rejected.map(r => `${r.name} (${r.reason})`).join(', ');
+          }
+          showAlert(message, summary.rejected.length > 0 ? 'warning' : 'success');
+          refreshData();
+        } else {
+          promptForApiKey(res);
+          showAlert('Error importing frontends: ' + await res.text(), 'danger');
+        }
+      } catch (err) {
+        showAlert('Error importing frontends: ' + err, 'danger');
+      }
+    }
+
+    async function deleteFrontend(name) {
+      try {
+        const res = await fetch('./delete_frontend', {
+          method: 'POST',
+          headers: mutationHeaders(),
+          body: new URLSearchParams({ name })
+        });
+        if (res.ok) {
+          showAlert('Frontend deleted successfully!', 'success');
+          refreshData();
+        } else {
+          promptForApiKey(res);
+          showAlert('Error deleting frontend: ' + await res.text(), 'danger');
+        }
+      } catch (err) {
+        showAlert('Error deleting frontend: ' + err, 'danger');
+      }
+    }
+
+    async function setMuted(name, muted) {
+      try {
+        const res = await fetch(muted ? './mute_frontend' : './unmute_frontend', {
+          method: 'POST',
+          headers: mutationHeaders(),
+          body: new URLSearchParams({ name })
+        });
+        if (res.ok) {
+          showAlert(muted ? 'Alerts muted.' : 'Alerts unmuted.', 'success');
+          refreshData();
+        } else {
+          promptForApiKey(res);
+          showAlert('Error updating frontend: ' + await res.text(), 'danger');
+        }
+      } catch (err) {
+        showAlert('Error updating frontend: ' + err, 'danger');
+      }
+    }
+
+    // Shows whether alerts are paused on the pause button, which toggles it.
+    let alertsPaused = false;
+    function renderAlertPause(state) {
+      alertsPaused = state.paused;
+      const btn = document.getElementById('pauseAlertsBtn');
+      if (!state.paused) {
+        btn.textContent = 'Pause Alerts';
+      } else if (state.resumes_in_secs != null) {
+        btn.textContent = `Resume Alerts (paused for ${Math.ceil(state.resumes_in_secs / 60)} more min)`;
+      } else {
+        btn.textContent = 'Resume Alerts (paused)';
+      }
+    }
+
+    async function loadAlertPause() {
+      try {
+        const res = await fetch('./api/alerts');
+        renderAlertPause(await res.json());
+      } catch (err) {
+        console.error('Error fetching alert pause state:', err);
+      }
+    }
+
+    async function toggleAlertPause() {
+      const body = new URLSearchParams();
+      if (!alertsPaused) {
+        const minutes = prompt('Pause all alerts for how many minutes? Leave empty to pause until resumed.');
+        if (minutes === null) return;
+        if (minutes.trim() !== '') {
+          body.set('minutes', minutes.trim());
+        }
+      }
+      try {
+        const res = await fetch(alertsPaused ? './resume_alerts' : './pause_alerts', {
+          method: 'POST',
+          headers: mutationHeaders(),
+          body
+        });
+        if (res.ok) {
+          const state = await res.json();
+          renderAlertPause(state);
+          showAlert(state.paused ? 'Alerts paused.' : 'Alerts resumed.', 'success');
+        } else {
+          promptForApiKey(res);
+          showAlert('Error updating alert pause: ' + await res.text(), 'danger');
+        }
+      } catch (err) {
+        showAlert('Error updating alert pause: ' + err, 'danger');
+      }
+    }
+
+    // The tab title and icon follow the server-side summary of every
+    // frontend, whatever the tag filter shows.
+    let worstStatus = null;
+    async function refreshHealth() {
+      try {
+        const res = await fetch('./api/summary');
+        const summary = await res.json();
+        if (summary.worst_status !== worstStatus) {
+          worstStatus = summary.worst_status;
+          document.title = (worstStatus === 'red' ? '\u{1F534}' : '\u{1F7E2}') + ' Monitoring';
+          document.getElementById('favicon').href = './favicon.ico?status=' + worstStatus;
+        }
+      } catch (err) {
+        console.error('Error fetching summary:', err);
+      }
+    }
+    refreshHealth();
+    setInterval(refreshHealth, __POLL_INTERVAL_MS__);
+
+    document.getElementById('pauseAlertsBtn').addEventListener('click', toggleAlertPause);
+    loadAlertPause();
+    setInterval(loadAlertPause, 60000);
+
+    document.getElementById('addFrontendBtn').addEventListener('click', () => {
+      new bootstrap.Modal(document.getElementById('addFrontendModal')).show();
+    });
+    document.getElementById('add-frontend-form').addEventListener('submit', addFrontend);
+    document.getElementById('importFrontendsBtn').addEventListener('click', () => {
+      document.getElementById('importFile').click();
+    });
+    document.getElementById('importFile').addEventListener('change', importFrontends);
+
+    // Prefer live updates over the event stream, and fall back to polling if
+    // the browser lacks EventSource or while the stream is down. A dropped
+    // stream is retried, waiting twice as long after each failure.
+    let pollTimer = null;
+    let streamSource = null;
+    let reconnectDelay = 1000;
+    function startPolling() {
+      if (pollTimer === null) {
+        refreshData();
+        pollTimer = setInterval(refreshData, __POLL_INTERVAL_MS__);
+      }
+    }
+    function stopPolling() {
+      if (pollTimer !== null) {
+        clearInterval(pollTimer);
+        pollTimer = null;
+      }
+    }
+    function connectStream() {
+      if (!window.EventSource) {
+        startPolling();
+        return;
+      }
+      const source = new EventSource('./api/stream' + serversQuery());
+      source.onopen = () => {
+        reconnectDelay = 1000;
+        stopPolling();
+      };
+      source.onmessage = (event) => renderServers(JSON.parse(event.data));
+      source.onerror = () => {
+        source.close();
+        streamSource = null;
+        startPolling();
+        setTimeout(connectStream, reconnectDelay);
+        reconnectDelay = Math.min(reconnectDelay * 2, 60000);
+      };
+      streamSource = source;
+    }
+    connectStream();
+    document.getElementById('tagFilter').addEventListener('change', () => {
+      if (streamSource) {
+        streamSource.close();
+        connectStream();
+      } else {
+        refreshData();
+      }
+    });
+  </script>
+</body>
+</html>
+"#;
+    let html = html
+        .replace("__POLL_INTERVAL_MS__", &(*POLL_INTERVAL_SECS * 1000).to_string())
+        .replace("__TZ_OFFSET__", &TZ_OFFSET.to_string());
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+// Errors shared by the handlers and the poll loop. Handlers return them with
+// `?` and actix turns them into a response with the matching status code.
+#[derive(Debug)]
+enum AppError {
+    Unauthorized,
+    BadRequest(String),
+    NotFound(&'static str),
+    Save { file: String, source: io::Error },
+    Database(String),
+}
+
+impl AppError {
+    fn save(file: &str, source: io::Error) -> Self {
+        AppError::Save { file: file.to_string(), source }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Unauthorized => f.write_str("Invalid or missing API key"),
+            AppError::BadRequest(msg) => f.write_str(msg),
+            AppError::NotFound(what) => write!(f, "{what} not found"),
+            AppError::Save { file, source } => write!(f, "Failed to write {file}: {source}"),
+            AppError::Database(msg) => write!(f, "Database query failed: {msg}"),
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> HttpStatus {
+        match self {
+            AppError::Unauthorized => HttpStatus::UNAUTHORIZED,
+            AppError::BadRequest(_) => HttpStatus::BAD_REQUEST,
+            AppError::NotFound(_) => HttpStatus::NOT_FOUND,
+            AppError::Save { .. } | AppError::Database(_) => HttpStatus::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AppError::Save { .. } => {
+                error!(error = %self, "Request failed");
+                // The in-memory change already happened, only persisting it failed.
+                HttpResponse::InternalServerError()
+                    .body(format!("{self}; the change is in effect until the backend restarts"))
+            }
+            AppError::Database(_) => {
+                error!(error = %self, "Request failed");
+                // The driver's message is only for the log.
+                HttpResponse::InternalServerError().body("Database query failed")
+            }
+            _ => HttpResponse::build(self.status_code()).body(self.to_string()),
+        }
+    }
+}
+
+// Mutating endpoints require a matching X-API-Key header when API_KEY is set.
+fn require_api_key(req: &HttpRequest) -> Result<(), AppError> {
+    if is_authorized(req) {
+        Ok(())
+    } else {
+        Err(AppError::Unauthorized)
+    }
+}
+
+fn is_authorized(req: &HttpRequest) -> bool {
+    let Some(api_key) = &*API_KEY else {
+        return true;
+    };
+    let provided = req
+        .headers()
+        .get("X-API-Key")
+        .map(|value| value.as_bytes())
+        .unwrap_or_default();
+    provided.ct_eq(api_key.as_bytes()).into()
+}
+
+// Checks that a frontend's address makes sense for its type.
+fn validate_frontend(name: &str, ip: &str, frontend_type: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Server name must not be empty".to_string());
+    }
+    let ip = ip.trim();
+    if ip.is_empty() {
+        return Err("IP/Address must not be empty".to_string());
+    }
+    let parse_http_url = |url: &str| match Url::parse(url) {
+        Ok(url) if !matches!(url.scheme(), "http" | "https") => {
+            Err(format!("Unsupported scheme '{}', expected http or https", url.scheme()))
+        }
+        Ok(url) if url.host_str().is_none_or(str::is_empty) => Err(format!("'{}' has no host", ip)),
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("'{}' is not a valid URL: {}", ip, e)),
+    };
+    match frontend_type.to_lowercase().as_str() {
+        "server" | "website" if ip.contains("://") => parse_http_url(ip),
+        "server" | "website" => parse_http_url(&format!("http://{}", ip)),
+        "port" => match ip.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => Ok(()),
+            _ => Err(format!("'{}' must be in host:port form", ip)),
+        },
+        "ping" | "dns" if ip.parse::<IpAddr>().is_err() && ip.contains([':', '/', ' ']) => {
+            Err(format!("'{}' must be a host name or IP address", ip))
+        }
+        "ping" | "dns" => Ok(()),
+        "service" => service_url(ip).map(|_| ()),
+        other => Err(format!("Unknown type '{}'", other)),
+    }
+}
+
+// Checks the Authorization header against DASHBOARD_USER/DASHBOARD_PASS.
+fn has_dashboard_credentials(req: &HttpRequest, user: &str, pass: &str) -> bool {
+    let Some(encoded) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+    else {
+        return false;
+    };
+    let Ok(decoded) = BASE64_STANDARD.decode(encoded.trim()) else {
+        return false;
+    };
+    let expected = format!("{}:{}", user, pass);
+    decoded.ct_eq(expected.as_bytes()).into()
+}
+
+// Requires HTTP basic auth on every route when DASHBOARD_USER and DASHBOARD_PASS
+// are set. /metrics can be left open for scrapers with METRICS_PUBLIC=true.
+async fn basic_auth(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let authorized = match &*DASHBOARD_CREDENTIALS {
+        None => true,
+        Some(_) if *METRICS_PUBLIC && req.path() == "/metrics" => true,
+        // Probes reveal nothing and usually cannot send credentials.
+        Some(_) if matches!(req.path(), "/health" | "/ready") => true,
+        Some((user, pass)) => has_dashboard_credentials(req.request(), user, pass),
+    };
+    if authorized {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    let response = HttpResponse::Unauthorized()
+        .insert_header((header::WWW_AUTHENTICATE, "Basic realm=\"Monitoring Dashboard\""))
+        .body("Unauthorized");
+    Ok(req.into_response(response).map_into_right_body())
+}
+
+// POST requests allowed per client IP and minute, 0 for no limit.
+static MUTATION_RATE_LIMIT: Lazy<u32> = Lazy::new(|| env_parse::<u32>("MUTATION_RATE_LIMIT", 60));
+static RATE_LIMIT_BUCKETS: Lazy<RwLock<HashMap<IpAddr, TokenBucket>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+// Holds up to `per_minute` tokens and regains them at `per_minute` a minute,
+// so a client can burst a full minute's worth and then has to slow down.
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, per_minute: u32, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_minute as f64 / 60.0).min(per_minute as f64);
+        self.updated = now;
+    }
+}
+
+// Takes a token for `ip`, returning false if it has none left.
+fn take_token(buckets: &mut HashMap<IpAddr, TokenBucket>, ip: IpAddr, per_minute: u32, now: Instant) -> bool {
+    // Full buckets carry no state, so drop them before the map grows large.
+    if buckets.len() > 1024 {
+        buckets.retain(|_, bucket| {
+            bucket.refill(per_minute, now);
+            bucket.tokens < per_minute as f64
+        });
+    }
+    let bucket = buckets.entry(ip).or_insert(TokenBucket { tokens: per_minute as f64, updated: now });
+    bucket.refill(per_minute, now);
+    if bucket.tokens < 1.0 {
+        return false;
+    }
+    bucket.tokens -= 1.0;
+    true
+}
+
+// Limits POST requests, which add, change or delete state, to
+// MUTATION_RATE_LIMIT per client IP and minute. Read-only requests are never
+// limited. Behind a reverse proxy every request shares the proxy's address.
+async fn rate_limit_mutations(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let limited = *MUTATION_RATE_LIMIT > 0
+        && *req.method() == actix_web::http::Method::POST
+        && req.peer_addr().is_some_and(|addr| {
+            !take_token(&mut RATE_LIMIT_BUCKETS.write_or_recover(), addr.ip(), *MUTATION_RATE_LIMIT, Instant::now())
+        });
+    if !limited {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    warn!(client = ?req.peer_addr(), path = %req.path(), "Rate limited a request");
+    let response = HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, (60 / *MUTATION_RATE_LIMIT).max(1).to_string()))
+        .body("Too many requests, try again later");
+    Ok(req.into_response(response).map_into_right_body())
+}
+
+// Checks a frontend that is about to be added, including that its name is
+// free. Names are compared trimmed and case-insensitively, since history and
+// alert state are keyed by name; existing near-duplicates are left alone.
+fn validate_new_frontend(frontends: &Frontends, info: &FrontendInfo) -> Result<(), String> {
+    validate_frontend(&info.name, &info.ip, &info.frontend_type)?;
+    if info.timeout_secs.is_some_and(|secs| !(1..=MAX_TIMEOUT_SECS).contains(&secs)) {
+        return Err(format!("timeout_secs must be between 1 and {}", MAX_TIMEOUT_SECS));
+    }
+    if info.interval_secs.is_some_and(|secs| !(1..=MAX_INTERVAL_SECS).contains(&secs)) {
+        return Err(format!("interval_secs must be between 1 and {}", MAX_INTERVAL_SECS));
+    }
+    let name = info.name.trim().to_lowercase();
+    if let Some(existing) = frontends.keys().find(|existing| existing.trim().to_lowercase() == name) {
+        return Err(format!("Frontend name '{}' conflicts with existing frontend '{}'", info.name.trim(), existing));
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct ImportSummary {
+    added: Vec<String>,
+    rejected: Vec<RejectedFrontend>,
+}
+
+#[derive(Debug, Serialize)]
+struct RejectedFrontend {
+    name: String, // "entry N" (1-based) if the entry has no name
+    reason: String,
+}
+
+// Splits one CSV line into fields, undoing csv_field's quoting.
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// Parses an upload into one result per entry, so a malformed entry only
+// rejects itself. A CSV upload needs a header row naming the columns, which
+// are the same fields as the add form.
+fn parse_import(body: &str, csv: bool) -> Result<Vec<Result<FrontendInfo, RejectedFrontend>>, String> {
+    if !csv {
+        let entries: Vec<serde_json::Value> =
+            serde_json::from_str(body).map_err(|e| format!("Expected a JSON array of frontends: {}", e))?;
+        return Ok(entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let name = match entry.get("name").and_then(|n| n.as_str()) {
+                    Some(name) => name.to_string(),
+                    None => format!("entry {}", i + 1),
+                };
+                serde_json::from_value(entry).map_err(|e| RejectedFrontend { name, reason: e.to_string() })
+            })
+            .collect());
+    }
+    let mut lines = body.lines().filter(|line| !line.trim().is_empty());
+    let columns: Vec<String> = match lines.next() {
+        Some(header) => parse_csv_line(header).into_iter().map(|c| c.trim().to_lowercase()).collect(),
+        None => return Err("CSV upload has no header row".to_string()),
+    };
+    Ok(lines
+        .enumerate()
+        .map(|(i, line)| {
+            let fields = parse_csv_line(line);
+            let name = columns
+                .iter()
+                .position(|c| c == "name")
+                .and_then(|pos| fields.get(pos))
+                .filter(|name| !name.is_empty())
+                .cloned()
+                .unwrap_or_else(|| format!("entry {}", i + 1));
+            // Same encoding as the add form, so numbers and tags parse the same way.
+            let pairs: Vec<(&str, &str)> = columns
+                .iter()
+                .zip(&fields)
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(column, value)| (column.as_str(), value.as_str()))
+                .collect();
+            serde_urlencoded::to_string(&pairs)
+                .map_err(|e| e.to_string())
+                .and_then(|encoded| serde_urlencoded::from_str(&encoded).map_err(|e| e.to_string()))
+                .map_err(|reason| RejectedFrontend { name, reason })
+        })
+        .collect())
+}
+
+// Adds every valid frontend from a JSON array or, with a text/csv content
+// type, a CSV file, and reports the ones it skipped.
+#[post("/import_frontends")]
+async fn import_frontends(req: HttpRequest, body: String) -> Result<HttpResponse, AppError> {
+    require_api_key(&req)?;
+    let csv = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/csv"));
+    let entries = parse_import(&body, csv).map_err(AppError::BadRequest)?;
+    let mut summary = ImportSummary { added: vec![], rejected: vec![] };
+    let (saved, changes) = {
+        let mut frontends = FRONTENDS.write_or_recover();
+        let frontends = Arc::make_mut(&mut frontends);
+        for entry in entries {
+            let mut info = match entry {
+                Ok(info) => info,
+                Err(rejected) => {
+                    summary.rejected.push(rejected);
+                    continue;
+                }
+            };
+            info.name = info.name.trim().to_string();
+            if let Err(reason) = validate_new_frontend(frontends, &info) {
+                summary.rejected.push(RejectedFrontend { name: info.name, reason });
+                continue;
             }
-          });
-          cpuTabItem.appendChild(cpuTab);
-          const cpuContent = document.createElement('div');
-          cpuContent.id = `cpu-content-${frontend.name}`;
-          cpuContent.className = 'tab-content';
-          let cpuHtml = "";
-          if (srv.cpu_usage != null) {
-            cpuHtml += `<p>Global CPU Usage: ${srv.cpu_usage.toFixed(2)}%</p>`;
-          }
-          if (srv.cpus != null && srv.cpus.length > 0) {
-            cpuHtml += `<table class="table table-striped">
-              <thead>
-                <tr>
-                  <th>CPU Core</th>
-                  <th>Usage (%)</th>
-                  <th>Frequency (MHz)</th>
-                  <th>Status</th>
-                </tr>
-              </thead>
-              <tbody>`;
-            srv.cpus.forEach(cpu => {
-              cpuHtml += `<tr>
-                <td>${cpu.name}</td>
-                <td>${cpu.cpu_usage.toFixed(2)}</td>
-                <td>${cpu.frequency}</td>
-                <td><span class="text-${cpu.status}">${cpu.status == "red" ? "&#x26A0;" : "&#x2714;"}</span></td>
-              </tr>`;
-            });
-            cpuHtml += `</tbody></table>`;
-          } else {
-            cpuHtml += `<p class="text-danger">Unable to retrieve CPU usage data.</p>`;
-          }
-          cpuContent.innerHTML = cpuHtml;
-          cpuContent.style.display = (window.expandedStates[frontend.name] === 'cpu') ? 'block' : 'none';
-          cpuTabItem.appendChild(cpuContent);
-          tabGroup.appendChild(cpuTabItem);
-          
-          const memoryTabItem = document.createElement('div');
-          memoryTabItem.className = 'tab-item';
-          const memoryTab = document.createElement('div');
-          memoryTab.className = 'tab';
-          const memoryTabIcon = srv.memory_status === 'red'
-            ? '<span class="red">&#x26A0;</span>'
-            : '<span class="green">&#x2714;</span>';
-          memoryTab.innerHTML = `Memory Usage ${memoryTabIcon}`;
-          memoryTab.addEventListener('click', () => {
-            if (window.expandedStates[frontend.name] === 'memory') {
-              window.expandedStates[frontend.name] = 'open';
-              memoryContent.style.display = 'none';
-            } else {
-              window.expandedStates[frontend.name] = 'memory';
-              memoryContent.style.display = 'block';
-              diskContent.style.display = 'none';
-              cpuContent.style.display = 'none';
+            summary.added.push(info.name.clone());
+            frontends.insert(info.name.clone(), info);
+        }
+        info!(added = summary.added.len(), rejected = summary.rejected.len(), "Imported frontends");
+        let saved = if summary.added.is_empty() { Ok(()) } else { save_frontends(frontends) };
+        (saved, summary.added.iter().map(|name| frontend_change(frontends, name)).collect())
+    };
+    share_frontend_changes(changes).await;
+    saved?;
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+#[post("/add_frontend")]
+async fn add_frontend(req: HttpRequest, form: web::Form<FrontendInfo>) -> Result<HttpResponse, AppError> {
+    require_api_key(&req)?;
+    let mut info = form.into_inner();
+    info.name = info.name.trim().to_string();
+    let (saved, change) = {
+        let mut frontends = FRONTENDS.write_or_recover();
+        let frontends = Arc::make_mut(&mut frontends);
+        validate_new_frontend(frontends, &info).map_err(AppError::BadRequest)?;
+        info!(frontend = %info.name, "Added frontend");
+        let name = info.name.clone();
+        frontends.insert(info.name.clone(), info);
+        (save_frontends(frontends), frontend_change(frontends, &name))
+    };
+    share_frontend_changes(vec![change]).await;
+    saved?;
+    Ok(HttpResponse::Ok().body("Added"))
+}
+
+#[post("/edit_frontend")]
+async fn edit_frontend(req: HttpRequest, form: web::Form<EditFrontend>) -> Result<HttpResponse, AppError> {
+    require_api_key(&req)?;
+    let info = form.into_inner();
+    validate_frontend(&info.name, &info.ip, &info.frontend_type).map_err(AppError::BadRequest)?;
+    let (saved, change) = {
+        let mut frontends = FRONTENDS.write_or_recover();
+        let frontends = Arc::make_mut(&mut frontends);
+        let frontend = frontends.get_mut(&info.name).ok_or(AppError::NotFound("Frontend"))?;
+        // Status history only makes sense for websites, so drop it if the type changes.
+        if frontend.frontend_type.to_lowercase() == "website" && info.frontend_type.to_lowercase() != "website" {
+            WEBSITE_HISTORY.write_or_recover().remove(&info.name);
+        }
+        if frontend.frontend_type.to_lowercase() == "server" && info.frontend_type.to_lowercase() != "server" {
+            SERVER_HISTORY.write_or_recover().remove(&info.name);
+        }
+        info!(frontend = %info.name, "Updated frontend");
+        frontend.ip = info.ip;
+        frontend.frontend_type = info.frontend_type;
+        (save_frontends(frontends), frontend_change(frontends, &info.name))
+    };
+    share_frontend_changes(vec![change]).await;
+    saved?;
+    Ok(HttpResponse::Ok().body("Updated"))
+}
+
+#[post("/delete_frontend")]
+async fn delete_frontend(req: HttpRequest, form: web::Form<DeleteFrontend>) -> Result<HttpResponse, AppError> {
+    require_api_key(&req)?;
+    let info = form.into_inner();
+    let saved = {
+        let mut frontends = FRONTENDS.write_or_recover();
+        let frontends = Arc::make_mut(&mut frontends);
+        frontends.shift_remove(&info.name);
+        info!(frontend = %info.name, "Deleted frontend");
+        forget_frontend(&info.name);
+        save_frontends(frontends)
+    };
+    share_frontend_changes(vec![(info.name, None)]).await;
+    saved?;
+    Ok(HttpResponse::Ok().body("Deleted"))
+}
+
+// Drops the in-memory state kept for a deleted frontend and closes its open
+// incidents.
+fn forget_frontend(name: &str) {
+    LAST_STATUSES.write_or_recover().remove(name);
+    LAST_ALERTS.write_or_recover().remove(name);
+    UPTIME_SAMPLES.write_or_recover().remove(name);
+    SERVER_HISTORY.write_or_recover().remove(name);
+    WEBSITE_HISTORY.write_or_recover().remove(name);
+    OUTDATED_AGENTS.write_or_recover().remove(name);
+    FLAP_STATES.write_or_recover().remove(name);
+    close_incidents(name);
+}
+
+// Closes a deleted frontend's open incidents, which no poll would ever close.
+fn close_incidents(name: &str) {
+    let now = Utc::now().with_timezone(&*TZ_OFFSET).format(CRAWL_TIME_FORMAT).to_string();
+    let mut closed = false;
+    for incident in INCIDENTS.write_or_recover().iter_mut().filter(|i| i.frontend == name && i.ended.is_none()) {
+        incident.ended = Some(now.clone());
+        closed = true;
+    }
+    if closed {
+        persist_incidents(vec![]);
+    }
+}
+
+async fn set_muted(name: &str, muted: bool) -> Result<HttpResponse, AppError> {
+    let (saved, change) = {
+        let mut frontends = FRONTENDS.write_or_recover();
+        let frontends = Arc::make_mut(&mut frontends);
+        let frontend = frontends.get_mut(name).ok_or(AppError::NotFound("Frontend"))?;
+        frontend.muted = muted;
+        info!(frontend = %name, muted, "Changed frontend alert muting");
+        // Reflect the change in the dashboard before the next poll.
+        let mut usage_data = USAGE_DATA.write_or_recover();
+        if let Some(usage) = Arc::make_mut(&mut usage_data).get_mut(name) {
+            usage.frontend.muted = muted;
+        }
+        (save_frontends(frontends), frontend_change(frontends, name))
+    };
+    share_frontend_changes(vec![change]).await;
+    saved?;
+    Ok(HttpResponse::Ok().body(if muted { "Muted" } else { "Unmuted" }))
+}
+
+#[post("/mute_frontend")]
+async fn mute_frontend(req: HttpRequest, form: web::Form<MuteFrontend>) -> Result<HttpResponse, AppError> {
+    require_api_key(&req)?;
+    set_muted(&form.name, true).await
+}
+
+#[post("/unmute_frontend")]
+async fn unmute_frontend(req: HttpRequest, form: web::Form<MuteFrontend>) -> Result<HttpResponse, AppError> {
+    require_api_key(&req)?;
+    set_muted(&form.name, false).await
+}
+
+// The current pause, ending a timed pause once it has run out.
+fn alert_pause() -> AlertPause {
+    let mut pause = ALERT_PAUSE.write_or_recover();
+    if matches!(*pause, AlertPause::Until(until) if Instant::now() >= until) {
+        *pause = AlertPause::Off;
+        info!("Alert pause ended, alerts resumed");
+    }
+    *pause
+}
+
+fn alerts_paused() -> bool {
+    !matches!(alert_pause(), AlertPause::Off)
+}
+
+#[derive(Serialize)]
+struct AlertPauseState {
+    paused: bool,
+    resumes_in_secs: Option<u64>, // None while paused indefinitely or not paused
+}
+
+fn alert_pause_state() -> AlertPauseState {
+    match alert_pause() {
+        AlertPause::Off => AlertPauseState { paused: false, resumes_in_secs: None },
+        AlertPause::Indefinite => AlertPauseState { paused: true, resumes_in_secs: None },
+        AlertPause::Until(until) => AlertPauseState {
+            paused: true,
+            resumes_in_secs: Some(until.saturating_duration_since(Instant::now()).as_secs()),
+        },
+    }
+}
+
+#[get("/api/alerts")]
+async fn api_alerts() -> impl Responder {
+    HttpResponse::Ok().json(alert_pause_state())
+}
+
+#[post("/pause_alerts")]
+async fn pause_alerts(req: HttpRequest, form: web::Form<PauseAlerts>) -> Result<HttpResponse, AppError> {
+    require_api_key(&req)?;
+    let pause = match form.minutes {
+        None => AlertPause::Indefinite,
+        Some(0) => return Err(AppError::BadRequest("minutes must be at least 1".to_string())),
+        Some(minutes) => minutes
+            .checked_mul(60)
+            .and_then(|secs| Instant::now().checked_add(Duration::from_secs(secs)))
+            .map(AlertPause::Until)
+            .ok_or_else(|| AppError::BadRequest(format!("Cannot pause alerts for {} minutes", minutes)))?,
+    };
+    share_alert_pause(pause).await;
+    *ALERT_PAUSE.write_or_recover() = pause;
+    info!(minutes = ?form.minutes, "Paused all alerts");
+    Ok(HttpResponse::Ok().json(alert_pause_state()))
+}
+
+#[post("/resume_alerts")]
+async fn resume_alerts(req: HttpRequest) -> Result<HttpResponse, AppError> {
+    require_api_key(&req)?;
+    share_alert_pause(AlertPause::Off).await;
+    *ALERT_PAUSE.write_or_recover() = AlertPause::Off;
+    info!("Resumed alerts");
+    Ok(HttpResponse::Ok().json(alert_pause_state()))
+}
+
+// A client builder with PROXY_URL and USER_AGENT applied, for every outbound
+// HTTP client.
+fn client_builder() -> ClientBuilder {
+    let builder = Client::builder().user_agent(USER_AGENT.clone());
+    match &*PROXY {
+        Some(proxy) => builder.proxy(proxy.clone()),
+        None => builder,
+    }
+}
+
+// Shared by every alert channel that posts over HTTP.
+static ALERT_CLIENT: Lazy<Client> = Lazy::new(|| {
+    client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build reqwest client")
+});
+
+// With ALERT_DRY_RUN set, logs what would be sent on a channel and returns
+// true so the caller skips sending it.
+fn dry_run(channel: &str, payload: &str) -> bool {
+    if *ALERT_DRY_RUN {
+        info!(channel, payload = %payload, "Dry run, not sending alert");
+    }
+    *ALERT_DRY_RUN
+}
+
+async fn send_slack_alert(message: &str) {
+    if let Some(webhook) = &*SLACK_WEBHOOK {
+        let payload = serde_json::json!({ "text": message });
+        if dry_run("slack", &payload.to_string()) {
+            return;
+        }
+        if let Err(e) = ALERT_CLIENT.post(webhook).json(&payload).send().await {
+            error!(error = %e, "Error sending slack alert");
+        }
+    } else {
+        warn!("Slack webhook not set");
+    }
+}
+
+async fn send_discord_alert(message: &str) {
+    if let Some(webhook) = &*DISCORD_WEBHOOK {
+        let payload = serde_json::json!({ "content": message });
+        if dry_run("discord", &payload.to_string()) {
+            return;
+        }
+        if let Err(e) = ALERT_CLIENT.post(webhook).json(&payload).send().await {
+            error!(error = %e, "Error sending discord alert");
+        }
+    } else {
+        warn!("Discord webhook not set");
+    }
+}
+
+async fn send_teams_alert(message: &str, status: Status) {
+    if let Some(webhook) = &*TEAMS_WEBHOOK {
+        let theme_color = match status {
+            Status::Green => "2EB886",
+            Status::Yellow => "F0AD4E",
+            Status::Red => "D9534F",
+        };
+        let payload = serde_json::json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "themeColor": theme_color,
+            "text": message,
+        });
+        if dry_run("teams", &payload.to_string()) {
+            return;
+        }
+        if let Err(e) = ALERT_CLIENT.post(webhook).json(&payload).send().await {
+            error!(error = %e, "Error sending teams alert");
+        }
+    } else {
+        warn!("Teams webhook not set");
+    }
+}
+
+async fn send_telegram_alert(message: &str) {
+    let (Some(token), Some(chat_id)) = (&*TELEGRAM_BOT_TOKEN, &*TELEGRAM_CHAT_ID) else {
+        warn!("Telegram bot token or chat id not set");
+        return;
+    };
+    let url = format!("https://api.telegram.org/bot{}/sendMessage", token);
+    let payload = serde_json::json!({ "chat_id": chat_id, "text": message });
+    if dry_run("telegram", &payload.to_string()) {
+        return;
+    }
+    // The token is part of the URL, so it is stripped from errors before logging.
+    match ALERT_CLIENT.post(&url).json(&payload).send().await {
+        Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after = resp
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|body| body["parameters"]["retry_after"].as_u64());
+            warn!(retry_after_secs = ?retry_after, "Telegram rate limited the alert, dropping it");
+        }
+        Ok(resp) if !resp.status().is_success() => {
+            error!(status = %resp.status(), "Telegram rejected the alert");
+        }
+        Ok(_) => {}
+        Err(e) => error!(error = %e.without_url(), "Error sending telegram alert"),
+    }
+}
+
+async fn send_email_alert(subject: &str, body: &str) {
+    let Some(alerter) = &*EMAIL_ALERTER else {
+        return;
+    };
+    let mut builder = Message::builder().from(alerter.from.clone()).subject(subject);
+    for to in &alerter.to {
+        builder = builder.to(to.clone());
+    }
+    let email = match builder.body(body.to_string()) {
+        Ok(email) => email,
+        Err(e) => {
+            error!(error = %e, "Error building alert email");
+            return;
+        }
+    };
+    if dry_run("email", &String::from_utf8_lossy(&email.formatted())) {
+        return;
+    }
+    if let Err(e) = alerter.mailer.send(email).await {
+        error!(error = %e, "Error sending email alert");
+    }
+}
+
+// Substitutes the placeholders with JSON-escaped values, so they are safe
+// inside the template's string literals.
+fn render_webhook_template(template: &str, message: &str, frontend: &str, status: Status, time: &str) -> String {
+    let escape = |value: &str| {
+        let quoted = serde_json::Value::from(value).to_string();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+    template
+        .replace("{message}", &escape(message))
+        .replace("{frontend}", &escape(frontend))
+        .replace("{status}", status.as_str())
+        .replace("{time}", &escape(time))
+}
+
+async fn send_webhook_alert(message: &str, frontend: &str, status: Status, time: &str) {
+    let Some(alerter) = &*WEBHOOK_ALERTER else {
+        return;
+    };
+    let payload = render_webhook_template(&alerter.template, message, frontend, status, time);
+    if dry_run("webhook", &payload) {
+        return;
+    }
+    let request = ALERT_CLIENT
+        .post(&alerter.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(payload);
+    if let Err(e) = request.send().await {
+        error!(error = %e, "Error sending webhook alert");
+    }
+}
+
+// Fans an alert out to every enabled channel.
+async fn send_alert(frontend: &str, status: Status, time: &str, message: &str) {
+    if !owns_poller_lock().await {
+        warn!(frontend, "Not sending an alert, this backend no longer holds the poller lock");
+        return;
+    }
+    let slack = async {
+        if *SLACK_ALERT_ENABLED {
+            send_slack_alert(message).await;
+        }
+    };
+    let discord = async {
+        if *DISCORD_ALERT_ENABLED {
+            send_discord_alert(message).await;
+        }
+    };
+    let teams = async {
+        if *TEAMS_ALERT_ENABLED {
+            send_teams_alert(message, status).await;
+        }
+    };
+    let telegram = async {
+        if *TELEGRAM_ALERT_ENABLED {
+            send_telegram_alert(message).await;
+        }
+    };
+    let subject = format!("Monitoring alert: {}", frontend);
+    let email = send_email_alert(&subject, message);
+    let webhook = send_webhook_alert(message, frontend, status, time);
+    futures::join!(slack, discord, teams, telegram, email, webhook);
+}
+
+/// Polls a single frontend, computes its status and sends alerts for any
+/// metric that has just turned red or for a frontend that has recovered.
+async fn poll_frontend(client: Client, fe: FrontendInfo) -> ServerUsage {
+    let crawl_time = Utc::now().with_timezone(&*TZ_OFFSET).format(CRAWL_TIME_FORMAT).to_string();
+
+    let (mut usage, failure) = match fe.frontend_type.to_lowercase().as_str() {
+        "server" => poll_server(&client, &fe, &crawl_time).await,
+        "website" => poll_website(&client, &fe, &crawl_time).await,
+        "port" => poll_port(&fe, &crawl_time).await,
+        "ping" => poll_ping(&fe, &crawl_time).await,
+        "dns" => poll_dns(&fe, &crawl_time).await,
+        "service" => poll_service(&client, &fe, &crawl_time).await,
+        _ => (ServerUsage::failed(&fe, &crawl_time, Status::Red), None),
+    };
+    usage.uptime_percent = record_uptime(&fe.name, usage.overall_status != Status::Red);
+
+    let changes = record_statuses(&usage);
+    record_incidents(&fe.name, &changes, &crawl_time);
+    let transitioned =
+        changes.newly_red.contains(&"overall_status") || changes.cleared.contains(&"overall_status");
+    let (flapping, flap_change) = record_flapping(&fe.name, transitioned, Instant::now());
+    usage.flapping = flapping;
+    if fe.muted || alerts_paused() {
+        return usage;
+    }
+    match flap_change {
+        FlapChange::Started => {
+            let message = format!(
+                "Flapping: {} changed status more than {} times in {} seconds at {}; further changes are not alerted until it settles",
+                fe.name, *FLAP_THRESHOLD, *FLAP_WINDOW_SECS, crawl_time
+            );
+            send_alert(&fe.name, Status::Red, &crawl_time, &message).await;
+            return usage;
+        }
+        FlapChange::Stopped => {
+            let message = format!(
+                "Settled: {} stopped flapping and is {} at {}",
+                fe.name,
+                usage.overall_status.as_str(),
+                crawl_time
+            );
+            send_alert(&fe.name, usage.overall_status, &crawl_time, &message).await;
+            return usage;
+        }
+        FlapChange::None if flapping => return usage,
+        FlapChange::None => {}
+    }
+    if !changes.newly_red.is_empty() && cooldown_elapsed(&fe.name) {
+        let alert_message = failure.unwrap_or_else(|| {
+            let mut message =
+                format!("Alert for {}: statuses [{}] are red at {}", fe.name, changes.newly_red.join(", "), crawl_time);
+            let values = red_metric_values(&usage);
+            if !values.is_empty() {
+                message.push_str(&format!(" ({})", values.join(" ")));
             }
-          });
-          memoryTabItem.appendChild(memoryTab);
-          const memoryContent = document.createElement('div');
-          memoryContent.id = `memory-content-${frontend.name}`;
-          memoryContent.className = 'tab-content';
-          let memoryHtml = "";
-          if (srv.memory_usage != null) {
-            memoryHtml += `<p>Total Memory: ${srv.memory_usage.total_memory}</p>`;
-            memoryHtml += `<p>Used Memory: ${srv.memory_usage.used_memory}</p>`;
-            memoryHtml += `<p>Usage: ${srv.memory_usage.memory_percent.toFixed(2)}%</p>`;
-          } else {
-            memoryHtml += `<p class="text-danger">Unable to retrieve memory usage data.</p>`;
-          }
-          memoryContent.innerHTML = memoryHtml;
-          memoryContent.style.display = (window.expandedStates[frontend.name] === 'memory') ? 'block' : 'none';
-          memoryTabItem.appendChild(memoryContent);
-          tabGroup.appendChild(memoryTabItem);
+            message
+        });
+        send_alert(&fe.name, Status::Red, &crawl_time, &alert_message).await;
+    }
+    if changes.recovered {
+        let recovery_message =
+            format!("Recovered: {} is back to {} at {}", fe.name, usage.overall_status.as_str(), crawl_time);
+        send_alert(&fe.name, usage.overall_status, &crawl_time, &recovery_message).await;
+    }
+    usage
+}
+
+// The readings behind each red metric, e.g. "cpu=96.2%" or "disk(/var)=97.0%",
+// so alerts can be triaged without opening the dashboard.
+fn red_metric_values(usage: &ServerUsage) -> Vec<String> {
+    let mut values = vec![];
+    if let (Status::Red, Some(cpu)) = (usage.cpu_status, usage.cpu_usage) {
+        values.push(format!("cpu={:.1}%", cpu));
+    }
+    if let Some(memory) = usage.memory_usage.as_ref().filter(|m| m.status == Status::Red) {
+        values.push(format!("memory={:.1}%", memory.memory_percent));
+    }
+    if let Some(swap) = usage.swap_usage.as_ref().filter(|s| s.status == Status::Red) {
+        values.push(format!("swap={:.1}%", swap.swap_percent));
+    }
+    for disk in usage.disk_usage.iter().flatten().filter(|d| d.status == Status::Red && !d.ignored) {
+        values.push(format!("disk({})={:.1}%", disk.mount_point, disk.used_percent));
+    }
+    for disk in usage.disk_usage.iter().flatten().filter(|d| d.inode_status == Some(Status::Red) && !d.ignored) {
+        values.push(format!("inodes({})={:.1}%", disk.mount_point, disk.inodes_percent.unwrap_or_default()));
+    }
+    for temp in usage.temperatures.iter().flatten() {
+        if TEMP_THRESHOLDS.status(temp.celsius as f64) == Status::Red {
+            values.push(format!("temp({})={:.1}C", temp.label, temp.celsius));
         }
-        
-        serverDiv.appendChild(tabGroup);
-        container.appendChild(serverDiv);
-      });
     }
+    if let (Status::Red, Some(battery)) = (usage.battery_status, &usage.battery) {
+        let source = if battery.on_ac { "" } else { " on battery" };
+        values.push(format!("battery={:.1}%{}", battery.percent, source));
+    }
+    for (i, gpu) in usage.gpus.iter().flatten().enumerate() {
+        if GPU_THRESHOLDS.status(gpu.utilization_percent as f64) == Status::Red {
+            values.push(format!("gpu({})={:.1}%", i, gpu.utilization_percent));
+        }
+    }
+    values
+}
 
-    async function refreshData() {
-      try {
-        const res = await fetch('./api/servers');
-        const data = await res.json();
-        renderServers(data);
-      } catch (err) {
-        console.error('Error fetching server data:', err);
-      }
+/// Sends a request, retrying transport errors up to POLL_RETRIES times with
+/// exponential backoff. All attempts share a single `timeout` budget so a
+/// dead host can't stall the batch.
+async fn send_with_retries(
+    name: &str,
+    timeout: Duration,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    // Without a representable deadline every attempt gets the full timeout.
+    let deadline = Instant::now().checked_add(timeout);
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        let remaining = deadline.map_or(timeout, |deadline| deadline.saturating_duration_since(Instant::now()));
+        let result = build().timeout(remaining).send().await;
+        let retry_in_time = || deadline.is_none_or(|deadline| Instant::now().checked_add(backoff).is_some_and(|at| at < deadline));
+        match result {
+            Err(err) if attempt < *POLL_RETRIES && retry_in_time() => {
+                attempt += 1;
+                debug!(frontend = %name, error = %err, attempt, "Request failed, retrying");
+                time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Builds the agent's metrics URL from a server's address. A missing scheme
+/// defaults to http with the agent's port, and a missing path to `/usage`, so
+/// `10.0.0.5` becomes `http://10.0.0.5:8081/usage`.
+fn agent_url(ip: &str) -> Result<Url, url::ParseError> {
+    let ip = ip.trim();
+    let has_scheme = ip.contains("://");
+    let mut url = if has_scheme { Url::parse(ip)? } else { Url::parse(&format!("http://{}", ip))? };
+    if !has_scheme && url.port().is_none() {
+        let _ = url.set_port(Some(AGENT_PORT));
+    }
+    if url.path() == "/" {
+        url.set_path("/usage");
+    }
+    Ok(url)
+}
+
+/// Builds the agent's service-state URL from a service frontend's address,
+/// `agent-address/unit`, so `10.0.0.5/nginx` becomes
+/// `http://10.0.0.5:8081/service/nginx`.
+fn service_url(ip: &str) -> Result<Url, String> {
+    let ip = ip.trim();
+    let (agent, unit) = ip
+        .rsplit_once('/')
+        .filter(|(agent, unit)| !agent.is_empty() && !agent.ends_with('/') && !unit.is_empty())
+        .ok_or_else(|| format!("'{}' must be in agent-address/unit form", ip))?;
+    let mut url = agent_url(agent).map_err(|e| format!("'{}' is not a valid agent address: {}", agent, e))?;
+    url.set_path(&format!("/service/{}", unit));
+    Ok(url)
+}
+
+fn agent_request(client: &Client, url: &Url) -> reqwest::RequestBuilder {
+    let request = client.get(url.clone());
+    match &*AGENT_TOKEN {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+// Parses "major.minor.patch", ignoring any pre-release or build suffix.
+// Missing minor or patch numbers count as zero.
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let core = version.trim().split(['-', '+']).next()?;
+    let mut parts = [0; 3];
+    for (i, part) in core.split('.').enumerate() {
+        *parts.get_mut(i)? = part.parse().ok()?;
+    }
+    Some(parts)
+}
+
+// Warns once when an agent is older than MIN_AGENT_VERSION, again only if it
+// is downgraded after being upgraded.
+fn check_agent_version(name: &str, version: Option<&str>) {
+    let Some(min) = *MIN_AGENT_VERSION else {
+        return;
+    };
+    let outdated = version.and_then(parse_version).is_none_or(|v| v < min);
+    let mut outdated_agents = OUTDATED_AGENTS.write_or_recover();
+    if !outdated {
+        outdated_agents.remove(name);
+    } else if outdated_agents.insert(name.to_string()) {
+        warn!(
+            frontend = %name,
+            version = version.unwrap_or("unknown"),
+            min_version = %format!("{}.{}.{}", min[0], min[1], min[2]),
+            "Agent is older than MIN_AGENT_VERSION"
+        );
+    }
+}
+
+// Appends to a history buffer, dropping the oldest entries beyond `max_len`.
+fn push_bounded<T>(buffer: &mut VecDeque<T>, item: T, max_len: usize) {
+    buffer.push_back(item);
+    while buffer.len() > max_len {
+        buffer.pop_front();
     }
+}
+
+// A byte count in binary units with one decimal, e.g. "1.8 TiB". Counts below
+// 1 KiB are printed as is.
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Fetches metrics from a server agent. Returns the usage along with a
+/// description of the failure, if the agent could not be read.
+async fn poll_server(client: &Client, fe: &FrontendInfo, crawl_time: &str) -> (ServerUsage, Option<String>) {
+    let failed = |connectivity, error_kind: &str| ServerUsage {
+        error_kind: Some(error_kind.to_string()),
+        ..ServerUsage::failed(fe, crawl_time, connectivity)
+    };
+    let url = match agent_url(&fe.ip) {
+        Ok(url) => url,
+        Err(err) => {
+            warn!(frontend = %fe.name, error = %err, "Invalid agent address");
+            let message = format!("Alert for {}: invalid agent address '{}' at {}", fe.name, fe.ip, crawl_time);
+            return (failed(Status::Red, "bad_address"), Some(message));
+        }
+    };
+    let resp = match send_with_retries(&fe.name, fe.timeout(), || agent_request(client, &url)).await {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) => {
+            warn!(frontend = %fe.name, status = %resp.status(), "Agent returned an error status");
+            let message = format!("Alert for {}: agent returned status {} at {}", fe.name, resp.status(), crawl_time);
+            return (failed(Status::Red, "bad_status"), Some(message));
+        }
+        Err(err) => {
+            warn!(frontend = %fe.name, error = %err, "Error contacting frontend");
+            let message = format!("Connectivity error for {}: Unable to reach at {}. Error: {}", fe.name, crawl_time, err);
+            return (failed(Status::Red, "unreachable"), Some(message));
+        }
+    };
+    let metrics = match resp.json::<SystemMetrics>().await {
+        Ok(metrics) => metrics,
+        // The agent is there but reports nothing usable, e.g. a 204 from a
+        // placeholder endpoint, so show it as degraded rather than down.
+        Err(err) if fe.reachability_only == Some(true) => {
+            warn!(frontend = %fe.name, error = %err, "Agent answered without metrics");
+            let usage = ServerUsage {
+                connectivity: Status::Green,
+                error_kind: Some("no_metrics".to_string()),
+                ..ServerUsage::with_status(fe, crawl_time, Status::Yellow)
+            };
+            return (usage, None);
+        }
+        Err(err) => {
+            warn!(frontend = %fe.name, error = %err, "Failed to parse agent JSON");
+            let message = format!("Alert for {}: Failed to parse JSON response at {}. Error: {}", fe.name, crawl_time, err);
+            return (failed(Status::Green, "parse_error"), Some(message));
+        }
+    };
+
+    let computed_disks: Vec<ComputedDiskUsage> =
+        metrics.disk_usage.into_iter().map(|d| {
+            ComputedDiskUsage {
+                ignored: fe.ignore_mounts.contains(&d.mount_point),
+                mount_point: d.mount_point,
+                filesystem: d.filesystem,
+                total: d.total,
+                used: d.used,
+                total_human: human_bytes(d.total),
+                used_human: human_bytes(d.used),
+                used_percent: d.used_percent,
+                status: DISK_THRESHOLDS.status(d.used_percent),
+                inodes_total: d.inodes_total,
+                inodes_used: d.inodes_used,
+                inodes_percent: d.inodes_percent,
+                inode_status: d.inodes_percent.map(|percent| INODE_THRESHOLDS.status(percent)),
+            }
+        }).collect();
+    let computed_cpus: Vec<ComputedCpuInfo> =
+        metrics.cpus.into_iter().map(|c| {
+            ComputedCpuInfo {
+                name: c.name,
+                cpu_usage: c.cpu_usage,
+                frequency: c.frequency,
+                status: CPU_THRESHOLDS.status(c.cpu_usage as f64),
+            }
+        }).collect();
+    let computed_memory = ComputedMemoryUsage {
+        total_memory: metrics.total_memory,
+        used_memory: metrics.used_memory,
+        total_human: human_bytes(metrics.total_memory),
+        used_human: human_bytes(metrics.used_memory),
+        memory_percent: metrics.memory_percent,
+        status: MEMORY_THRESHOLDS.status(metrics.memory_percent),
+    };
+    let computed_swap = ComputedSwapUsage {
+        total_swap: metrics.total_swap,
+        used_swap: metrics.used_swap,
+        total_human: human_bytes(metrics.total_swap),
+        used_human: human_bytes(metrics.used_swap),
+        swap_percent: metrics.swap_percent,
+        status: SWAP_THRESHOLDS.status(metrics.swap_percent),
+    };
+    check_agent_version(&fe.name, metrics.version.as_deref());
+    let disk_status = Status::worst(computed_disks.iter().filter(|d| !d.ignored).map(|d| d.status));
+    let inode_status = Status::worst(computed_disks.iter().filter(|d| !d.ignored).filter_map(|d| d.inode_status));
+    let cpu_status = CPU_THRESHOLDS.status(metrics.cpu_usage as f64);
+    let memory_status = computed_memory.status;
+    let swap_status = computed_swap.status;
+    let temp_status = Status::worst(metrics.temperatures.iter().map(|t| TEMP_THRESHOLDS.status(t.celsius as f64)));
+    let battery_status = battery_status(metrics.battery.as_ref());
+    let gpu_status = Status::worst(metrics.gpus.iter().map(|g| GPU_THRESHOLDS.status(g.utilization_percent as f64)));
+    let overall_status = Status::worst([
+        disk_status,
+        inode_status,
+        cpu_status,
+        memory_status,
+        swap_status,
+        temp_status,
+        battery_status,
+        gpu_status,
+    ]);
+    push_bounded(
+        SERVER_HISTORY.write_or_recover().entry(fe.name.clone()).or_default(),
+        ServerSample {
+            crawl_time: crawl_time.to_string(),
+            cpu_usage: metrics.cpu_usage,
+            memory_percent: metrics.memory_percent,
+            disk_max: computed_disks.iter().filter(|d| !d.ignored).map(|d| d.used_percent).reduce(f64::max),
+        },
+        *SERVER_HISTORY_LENGTH,
+    );
+
+    let usage = ServerUsage {
+        disk_usage: Some(computed_disks),
+        cpu_usage: Some(metrics.cpu_usage),
+        cpus: Some(computed_cpus),
+        memory_usage: Some(computed_memory),
+        swap_usage: Some(computed_swap),
+        networks: Some(metrics.networks),
+        load_avg: Some(metrics.load_avg),
+        disk_io: Some(metrics.disk_io),
+        temperatures: Some(metrics.temperatures),
+        battery: metrics.battery,
+        users: Some(metrics.users),
+        gpus: Some(metrics.gpus),
+        host: Some(HostInfo {
+            hostname: metrics.hostname,
+            os_name: metrics.os_name,
+            os_version: metrics.os_version,
+            kernel_version: metrics.kernel_version,
+        }),
+        agent_version: metrics.version,
+        disk_status,
+        inode_status,
+        cpu_status,
+        memory_status,
+        swap_status,
+        temp_status,
+        battery_status,
+        gpu_status,
+        overall_status,
+        ..ServerUsage::with_status(fe, crawl_time, Status::Green)
+    };
+    (usage, None)
+}
+
+/// Asks an agent built with the `systemd` feature whether a unit is active.
+/// Any other state, such as "failed" or "inactive", is red.
+async fn poll_service(client: &Client, fe: &FrontendInfo, crawl_time: &str) -> (ServerUsage, Option<String>) {
+    let failed = |connectivity, error_kind: &str| ServerUsage {
+        error_kind: Some(error_kind.to_string()),
+        ..ServerUsage::failed(fe, crawl_time, connectivity)
+    };
+    let url = match service_url(&fe.ip) {
+        Ok(url) => url,
+        Err(err) => {
+            warn!(frontend = %fe.name, error = %err, "Invalid service address");
+            let message = format!("Alert for {}: invalid service address '{}' at {}", fe.name, fe.ip, crawl_time);
+            return (failed(Status::Red, "bad_address"), Some(message));
+        }
+    };
+    let resp = match send_with_retries(&fe.name, fe.timeout(), || agent_request(client, &url)).await {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) => {
+            warn!(frontend = %fe.name, status = %resp.status(), "Agent returned an error status");
+            let message = format!("Alert for {}: agent returned status {} at {}", fe.name, resp.status(), crawl_time);
+            return (failed(Status::Red, "bad_status"), Some(message));
+        }
+        Err(err) => {
+            warn!(frontend = %fe.name, error = %err, "Error contacting frontend");
+            let message = format!("Connectivity error for {}: Unable to reach at {}. Error: {}", fe.name, crawl_time, err);
+            return (failed(Status::Red, "unreachable"), Some(message));
+        }
+    };
+    let state = match resp.json::<ServiceState>().await {
+        Ok(service) => service.state,
+        Err(err) => {
+            warn!(frontend = %fe.name, error = %err, "Failed to parse agent JSON");
+            let message = format!("Alert for {}: Failed to parse JSON response at {}. Error: {}", fe.name, crawl_time, err);
+            return (failed(Status::Green, "parse_error"), Some(message));
+        }
+    };
+    if state == "active" {
+        let usage = ServerUsage {
+            service_state: Some(state),
+            ..ServerUsage::with_status(fe, crawl_time, Status::Green)
+        };
+        return (usage, None);
+    }
+    warn!(frontend = %fe.name, state = %state, "Service is not active");
+    let message = format!("Alert for {}: service {} is {} at {}", fe.name, url.path().trim_start_matches("/service/"), state, crawl_time);
+    let usage = ServerUsage {
+        service_state: Some(state),
+        ..ServerUsage::failed(fe, crawl_time, Status::Green)
+    };
+    (usage, Some(message))
+}
+
+/// Checks a website's HTTP status code and appends it to the status history.
+async fn poll_website(client: &Client, fe: &FrontendInfo, crawl_time: &str) -> (ServerUsage, Option<String>) {
+    let url = if fe.ip.starts_with("http://") || fe.ip.starts_with("https://") {
+        fe.ip.clone()
+    } else {
+        format!("http://{}", fe.ip)
+    };
+    let (method, headers) = website_request_parts(fe);
+    let started = Instant::now();
+    let mut content_missing = false;
+    let request = || {
+        let mut request = client.request(method.clone(), &url).headers(headers.clone());
+        if let Some(body) = &fe.body {
+            request = request.body(body.clone());
+        }
+        match &fe.basic_auth_user {
+            Some(user) => request.basic_auth(user, fe.basic_auth_pass.as_ref().map(|pass| &pass.0)),
+            None => request,
+        }
+    };
+    let (website_status_code, response_ms, cert_days_remaining) = match send_with_retries(&fe.name, fe.timeout(), request).await {
+        Ok(resp) => {
+            let response_ms = started.elapsed().as_millis() as u64;
+            let status_code = resp.status().as_u16();
+            let cert_days = cert_days_remaining(&resp);
+            if let Some(expected) = &fe.expected_content {
+                content_missing = !body_contains(resp, expected).await;
+            }
+            (status_code, Some(response_ms), cert_days)
+        }
+        Err(err) => {
+            warn!(frontend = %fe.name, error = %err, "Error contacting website");
+            (0, None, None)
+        }
+    };
+    let status_ok = match &fe.expected_status {
+        Some(expected) => expected.matches(website_status_code),
+        None => website_status_code == 200,
+    };
+    let website_status = if status_ok && !content_missing { Status::Green } else { Status::Red };
+    let connectivity = if website_status_code != 0 { Status::Green } else { Status::Red };
+    let status_record = StatusRecord {
+        status_code: website_status_code,
+        crawl_time: crawl_time.to_string(),
+        response_ms,
+    };
+    // Snapshot the history under the same lock as the push so it includes this record.
+    let history: Vec<StatusRecord> = {
+        let mut history_map = WEBSITE_HISTORY.write_or_recover();
+        let history = history_map.entry(fe.name.clone()).or_default();
+        push_bounded(history, status_record, *HISTORY_LENGTH);
+        history.iter().cloned().collect()
+    };
+    let cert_expiring = cert_days_remaining.is_some_and(|days| days < *CERT_WARN_DAYS);
+    let failure = if !status_ok {
+        Some(format!("Alert for {}: website returned status {} at {}", fe.name, website_status_code, crawl_time))
+    } else if content_missing {
+        Some(format!("Alert for {}: expected content not found in response at {}", fe.name, crawl_time))
+    } else if cert_expiring {
+        Some(format!(
+            "Alert for {}: TLS certificate expires in {} days at {}",
+            fe.name,
+            cert_days_remaining.unwrap_or_default(),
+            crawl_time
+        ))
+    } else {
+        None
+    };
+    let usage = ServerUsage {
+        overall_status: if cert_expiring { Status::Red } else { website_status },
+        connectivity,
+        status_history: Some(history),
+        cert_days_remaining,
+        ..ServerUsage::with_status(fe, crawl_time, website_status)
+    };
+    (usage, failure)
+}
 
-    async function addFrontend(event) {
-      event.preventDefault();
-      const formData = new FormData(document.getElementById('add-frontend-form'));
-      try {
-        const res = await fetch('./add_frontend', {
-          method: 'POST',
-          headers: { 'Content-Type': 'application/x-www-form-urlencoded' },
-          body: new URLSearchParams({
-            name: formData.get('name'),
-            ip: formData.get('ip'),
-            type: formData.get('type')
-          })
-        });
-        if (res.ok) {
-          document.getElementById('add-frontend-form').reset();
-          const modalEl = document.getElementById('addFrontendModal');
-          const modal = bootstrap.Modal.getInstance(modalEl);
-          modal.hide();
-          showAlert('Frontend added successfully!', 'success');
-          refreshData();
-        } else {
-          showAlert('Error adding frontend: ' + await res.text(), 'danger');
+// The method and headers configured for a website check. An unknown method
+// falls back to the default and invalid headers are skipped, each with a warning.
+fn website_request_parts(fe: &FrontendInfo) -> (Method, HeaderMap) {
+    let default_method = if fe.body.is_some() { Method::POST } else { Method::GET };
+    let method = match &fe.method {
+        Some(method) => Method::from_bytes(method.trim().to_uppercase().as_bytes()).unwrap_or_else(|_| {
+            warn!(frontend = %fe.name, method = %method, "Invalid HTTP method, using {}", default_method);
+            default_method
+        }),
+        None => default_method,
+    };
+    let mut headers = HeaderMap::new();
+    for (name, value) in fe.headers.iter().flatten() {
+        match (HeaderName::from_bytes(name.trim().as_bytes()), HeaderValue::from_str(&value.0)) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => warn!(frontend = %fe.name, header = %name, "Skipping invalid request header"),
         }
-      } catch (err) {
-        showAlert('Error adding frontend: ' + err, 'danger');
-      }
     }
+    if fe.body.is_some() && !headers.contains_key(reqwest::header::CONTENT_TYPE) {
+        headers.insert(reqwest::header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    }
+    (method, headers)
+}
 
-    async function deleteFrontend(name) {
-      try {
-        const res = await fetch('./delete_frontend', {
-          method: 'POST',
-          headers: { 'Content-Type': 'application/x-www-form-urlencoded' },
-          body: new URLSearchParams({ name })
-        });
-        if (res.ok) {
-          showAlert('Frontend deleted successfully!', 'success');
-          refreshData();
-        } else {
-          showAlert('Error deleting frontend: ' + await res.text(), 'danger');
+// Reads at most MAX_BODY_BYTES of a response body and checks it for the expected text.
+async fn body_contains(mut resp: reqwest::Response, expected: &str) -> bool {
+    let mut body = Vec::new();
+    while body.len() < MAX_BODY_BYTES {
+        match resp.chunk().await {
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            Ok(None) => break,
+            Err(err) => {
+                warn!(error = %err, "Error reading response body");
+                break;
+            }
         }
-      } catch (err) {
-        showAlert('Error deleting frontend: ' + err, 'danger');
-      }
     }
+    body.truncate(MAX_BODY_BYTES);
+    String::from_utf8_lossy(&body).contains(expected)
+}
 
-    document.getElementById('addFrontendBtn').addEventListener('click', () => {
-      new bootstrap.Modal(document.getElementById('addFrontendModal')).show();
-    });
-    document.getElementById('add-frontend-form').addEventListener('submit', addFrontend);
+// Days until the peer certificate of an https response expires.
+fn cert_days_remaining(resp: &reqwest::Response) -> Option<i64> {
+    let der = resp.extensions().get::<TlsInfo>()?.peer_certificate()?;
+    let (_, cert) = parse_x509_certificate(der).ok()?;
+    let seconds = cert.validity().not_after.timestamp() - Utc::now().timestamp();
+    Some(seconds.div_euclid(86400))
+}
 
-    refreshData();
-    setInterval(refreshData, 5000);
-  </script>
-</body>
-</html>
-"#;
-    HttpResponse::Ok().content_type("text/html").body(html)
+/// Records a poll outcome and returns the frontend's uptime percentage over
+/// the rolling UPTIME_WINDOW_SECS window.
+fn record_uptime(name: &str, up: bool) -> f64 {
+    let now = Instant::now();
+    let window = Duration::from_secs(*UPTIME_WINDOW_SECS);
+    let mut samples_map = UPTIME_SAMPLES.write_or_recover();
+    let samples = samples_map.entry(name.to_string()).or_default();
+    samples.push_back(UptimeSample { at: now, up });
+    while samples.front().is_some_and(|sample| now.duration_since(sample.at) > window) {
+        samples.pop_front();
+    }
+    let up_count = samples.iter().filter(|sample| sample.up).count();
+    up_count as f64 / samples.len() as f64 * 100.0
 }
 
-#[post("/add_frontend")]
-async fn add_frontend(form: web::Form<FrontendInfo>) -> impl Responder {
-    let info = form.into_inner();
-    let mut frontends = FRONTENDS.write().unwrap();
-    if frontends.iter().any(|f| f.name == info.name) {
-        return HttpResponse::BadRequest().body("Frontend name already exists");
+// Whether a frontend started or stopped flapping on this poll.
+#[derive(Debug, PartialEq)]
+enum FlapChange {
+    Started,
+    Stopped,
+    None,
+}
+
+/// Records whether the overall status turned red or back on this poll and
+/// returns whether the frontend is flapping now, and whether that changed.
+fn record_flapping(name: &str, transitioned: bool, now: Instant) -> (bool, FlapChange) {
+    if *FLAP_THRESHOLD == 0 {
+        return (false, FlapChange::None);
+    }
+    let window = Duration::from_secs(*FLAP_WINDOW_SECS);
+    let mut states = FLAP_STATES.write_or_recover();
+    let state = states.entry(name.to_string()).or_default();
+    if transitioned {
+        state.transitions.push_back(now);
     }
-    frontends.push(info.clone());
-    if let Err(e) = save_frontends(&frontends) {
-        eprintln!("Failed to save frontends: {}", e);
+    while state.transitions.front().is_some_and(|at| now.duration_since(*at) > window) {
+        state.transitions.pop_front();
     }
-    HttpResponse::Ok().body("Added")
+    let flapping = state.transitions.len() > *FLAP_THRESHOLD;
+    let change = match (state.flapping, flapping) {
+        (false, true) => FlapChange::Started,
+        (true, false) => FlapChange::Stopped,
+        _ => FlapChange::None,
+    };
+    state.flapping = flapping;
+    (flapping, change)
 }
 
-#[post("/delete_frontend")]
-async fn delete_frontend(form: web::Form<DeleteFrontend>) -> impl Responder {
-    let info = form.into_inner();
-    let mut frontends = FRONTENDS.write().unwrap();
-    frontends.retain(|f| f.name != info.name);
-    if let Err(e) = save_frontends(&frontends) {
-        eprintln!("Failed to save frontends: {}", e);
+/// Returns whether a red alert may be sent for a frontend, and if so records
+/// it as the frontend's latest alert. Recovery alerts skip this check.
+fn cooldown_elapsed(name: &str) -> bool {
+    let cooldown = Duration::from_secs(*ALERT_COOLDOWN_SECS);
+    let mut last_alerts = LAST_ALERTS.write_or_recover();
+    let now = Instant::now();
+    if last_alerts.get(name).is_some_and(|last| now.duration_since(*last) < cooldown) {
+        return false;
     }
-    HttpResponse::Ok().body("Deleted")
+    last_alerts.insert(name.to_string(), now);
+    true
 }
 
-async fn send_slack_alert(message: &str) {
-    if let Some(webhook) = &*SLACK_WEBHOOK {
-		let client = Client::builder()
-			.timeout(Duration::from_secs(10))
-			.build()
-			.expect("Failed to build reqwest client");
+// Status keys that turned red, and whether the frontend as a whole recovered,
+// since the previous poll.
+struct StatusChanges {
+    newly_red: Vec<&'static str>,
+    cleared: Vec<&'static str>,
+    recovered: bool,
+}
 
-        let payload = serde_json::json!({ "text": message });
-        if let Err(e) = client.post(webhook).json(&payload).send().await {
-            eprintln!("Error sending slack alert: {}", e);
+// Identifiers for outgoing echo requests, so concurrent pings can be told apart.
+static PING_IDENTIFIER: AtomicU16 = AtomicU16::new(0);
+// Set once a ping has failed for lack of permission, so it is only logged once.
+static PING_PERMISSION_LOGGED: AtomicBool = AtomicBool::new(false);
+
+/// Sends ICMP echo requests to a host, retrying up to POLL_RETRIES times.
+async fn poll_ping(fe: &FrontendInfo, crawl_time: &str) -> (ServerUsage, Option<String>) {
+    let fail = |err: String| {
+        warn!(frontend = %fe.name, error = %err, "Ping failed");
+        let message = format!("Connectivity error for {}: No ping reply from {} at {}. Error: {}", fe.name, fe.ip, crawl_time, err);
+        (ServerUsage::failed(fe, crawl_time, Status::Red), Some(message))
+    };
+    let host = match fe.ip.trim().parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => match tokio::net::lookup_host((fe.ip.trim(), 0)).await.map(|mut addrs| addrs.next()) {
+            Ok(Some(addr)) => addr.ip(),
+            Ok(None) => return fail("host name has no addresses".to_string()),
+            Err(err) => return fail(err.to_string()),
+        },
+    };
+    let config = match host {
+        IpAddr::V4(_) => surge_ping::Config::default(),
+        IpAddr::V6(_) => surge_ping::Config::builder().kind(surge_ping::ICMP::V6).build(),
+    };
+    let client = match surge_ping::Client::new(&config) {
+        Ok(client) => client,
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            if !PING_PERMISSION_LOGGED.swap(true, Ordering::Relaxed) {
+                error!(error = %err, "Not permitted to send ICMP echo requests; ping frontends will be reported red");
+            }
+            let message = format!("Connectivity error for {}: Not permitted to ping at {}", fe.name, crawl_time);
+            return (ServerUsage::failed(fe, crawl_time, Status::Red), Some(message));
         }
-    } else {
-        eprintln!("Slack webhook not set");
+        Err(err) => return fail(err.to_string()),
+    };
+    let identifier = surge_ping::PingIdentifier(PING_IDENTIFIER.fetch_add(1, Ordering::Relaxed));
+    let mut pinger = client.pinger(host, identifier).await;
+    pinger.timeout(fe.timeout() / (*POLL_RETRIES + 1));
+    let mut last_error = None;
+    for sequence in 0..=*POLL_RETRIES {
+        match pinger.ping(surge_ping::PingSequence(sequence as u16), &[0; 8]).await {
+            Ok((_, rtt)) => {
+                let usage = ServerUsage {
+                    ping_ms: Some(rtt.as_secs_f64() * 1000.0),
+                    ..ServerUsage::with_status(fe, crawl_time, Status::Green)
+                };
+                return (usage, None);
+            }
+            Err(err) => {
+                debug!(frontend = %fe.name, attempt = sequence + 1, error = %err, "Ping attempt failed");
+                last_error = Some(err);
+            }
+        }
+    }
+    fail(last_error.map(|err| err.to_string()).unwrap_or_default())
+}
+
+/// Resolves a host name, which is red if resolution fails or finds no addresses.
+async fn poll_dns(fe: &FrontendInfo, crawl_time: &str) -> (ServerUsage, Option<String>) {
+    let failure = match time::timeout(fe.timeout(), tokio::net::lookup_host((fe.ip.trim(), 0))).await {
+        Ok(Ok(addrs)) => {
+            let mut ips: Vec<String> = Vec::new();
+            for addr in addrs {
+                let ip = addr.ip().to_string();
+                if !ips.contains(&ip) {
+                    ips.push(ip);
+                }
+            }
+            if !ips.is_empty() {
+                let usage = ServerUsage {
+                    resolved_ips: Some(ips),
+                    ..ServerUsage::with_status(fe, crawl_time, Status::Green)
+                };
+                return (usage, None);
+            }
+            "no addresses found".to_string()
+        }
+        Ok(Err(err)) => err.to_string(),
+        Err(_) => "lookup timed out".to_string(),
+    };
+    warn!(frontend = %fe.name, error = %failure, "DNS resolution failed");
+    let message = format!("DNS error for {}: Unable to resolve {} at {}. Error: {}", fe.name, fe.ip, crawl_time, failure);
+    let usage = ServerUsage {
+        resolved_ips: Some(vec![]),
+        ..ServerUsage::failed(fe, crawl_time, Status::Red)
+    };
+    (usage, Some(message))
+}
+
+/// Checks that a TCP port (`host:port` in the ip field) accepts connections.
+async fn poll_port(fe: &FrontendInfo, crawl_time: &str) -> (ServerUsage, Option<String>) {
+    let failure = match time::timeout(fe.timeout(), TcpStream::connect(&fe.ip)).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(_) => Some("connection timed out".to_string()),
+    };
+    let status = if failure.is_none() { Status::Green } else { Status::Red };
+    let failure = failure.map(|err| {
+        warn!(frontend = %fe.name, error = %err, "Error connecting to port");
+        format!("Connectivity error for {}: Unable to connect to {} at {}. Error: {}", fe.name, fe.ip, crawl_time, err)
+    });
+    (ServerUsage::with_status(fe, crawl_time, status), failure)
+}
+
+/// Stores the latest statuses of a frontend and reports what changed since the
+/// previous poll. A frontend seen for the first time is treated as previously
+/// green, so it can never be reported as recovered.
+fn record_statuses(usage: &ServerUsage) -> StatusChanges {
+    let statuses = [
+        ("connectivity", usage.connectivity),
+        ("disk_status", usage.disk_status),
+        ("inode_status", usage.inode_status),
+        ("cpu_status", usage.cpu_status),
+        ("memory_status", usage.memory_status),
+        ("swap_status", usage.swap_status),
+        ("temp_status", usage.temp_status),
+        ("battery_status", usage.battery_status),
+        ("gpu_status", usage.gpu_status),
+        ("overall_status", usage.overall_status),
+    ];
+    let mut last_statuses = LAST_STATUSES.write_or_recover();
+    let previous = last_statuses.entry(usage.frontend.name.clone()).or_default();
+    let mut changes = StatusChanges { newly_red: vec![], cleared: vec![], recovered: false };
+    for (key, status) in statuses {
+        let was_red = previous.insert(key.to_string(), status) == Some(Status::Red);
+        if status == Status::Red && !was_red {
+            changes.newly_red.push(key);
+        }
+        if status != Status::Red && was_red {
+            changes.cleared.push(key);
+        }
+        if key == "overall_status" && status != Status::Red && was_red {
+            changes.recovered = true;
+        }
+    }
+    changes
+}
+
+// Opens an incident for each status that turned red and closes the open
+// incidents for each one that cleared, saving the log when it changed.
+fn record_incidents(name: &str, changes: &StatusChanges, crawl_time: &str) {
+    if changes.newly_red.is_empty() && changes.cleared.is_empty() {
+        return;
     }
+    // A poll still in flight when its frontend was deleted would otherwise
+    // open incidents that nothing closes.
+    if !FRONTENDS.read_or_recover().contains_key(name) {
+        return;
+    }
+    let archivable = {
+        let mut incidents = INCIDENTS.write_or_recover();
+        for incident in incidents
+            .iter_mut()
+            .filter(|i| i.frontend == name && i.ended.is_none() && changes.cleared.contains(&i.metric.as_str()))
+        {
+            incident.ended = Some(crawl_time.to_string());
+        }
+        for metric in &changes.newly_red {
+            incidents.push(Incident {
+                frontend: name.to_string(),
+                metric: metric.to_string(),
+                started: crawl_time.to_string(),
+                ended: None,
+            });
+        }
+        take_archivable(&mut incidents)
+    };
+    persist_incidents(archivable);
 }
 
-async fn poll_frontends() {
-	let client = Client::builder()
-		.timeout(Duration::from_secs(10))
-		.build()
-		.expect("Failed to build reqwest client");
+// Redirect handling and certificate checks are fixed per client, so frontends
+// share one client per combination. Every request sets its own timeout, from
+// the frontend's timeout_secs.
+fn poll_client(follow_redirects: bool, insecure: bool) -> Client {
+    let mut builder = client_builder().tls_info(true).danger_accept_invalid_certs(insecure);
+    if !follow_redirects {
+        builder = builder.redirect(redirect::Policy::none());
+    }
+    builder.build().expect("Failed to build reqwest client")
+}
 
+async fn poll_frontends(mut shutdown: watch::Receiver<bool>) {
+    let mut clients: HashMap<(bool, bool), Client> = HashMap::new();
+    // Each frontend is polled on its own interval, so track when each was last polled.
+    let mut last_polled: HashMap<String, Instant> = HashMap::new();
+    let mut poller = false;
+    let mut shared_usage: Option<String> = None;
+    let mut cache_saved: Option<Instant> = None;
+    let keeper = REDIS.is_some().then(|| tokio::spawn(keep_poller_lock(shutdown.clone())));
     loop {
-        let frontends = FRONTENDS.read().unwrap().clone();
-        let new_usage_data: Vec<ServerUsage> = stream::iter(frontends.into_iter())
-            .map(|fe| {
-                let client = client.clone();
-                async move {
-                    let crawl_time = Utc::now()
-                        .with_timezone(&FixedOffset::east_opt(7 * 3600).unwrap())
-                        .format("%Y-%m-%d %H:%M:%S")
-                        .to_string();
-                    
-                    if fe.frontend_type.to_lowercase() == "server" {
-                        let url = format!("{}", fe.ip);
-                        let usage = match client.get(&url).send().await {
-                            Ok(resp) if resp.status().is_success() => {
-                                match resp.json::<SystemMetrics>().await {
-                                    Ok(metrics) => {
-                                        let computed_disks: Vec<ComputedDiskUsage> =
-                                            metrics.disk_usage.into_iter().map(|d| {
-                                                ComputedDiskUsage {
-                                                    mount_point: d.mount_point,
-                                                    total: d.total,
-                                                    used: d.used,
-                                                    used_percent: d.used_percent,
-                                                    status: if d.used_percent > 90.0 { "red".to_string() } else { "green".to_string() },
-                                                }
-                                            }).collect();
-                                        let computed_cpus: Vec<ComputedCpuInfo> =
-                                            metrics.cpus.into_iter().map(|c| {
-                                                ComputedCpuInfo {
-                                                    name: c.name,
-                                                    cpu_usage: c.cpu_usage,
-                                                    frequency: c.frequency,
-                                                    status: if c.cpu_usage > 90.0 { "red".to_string() } else { "green".to_string() },
-                                                }
-                                            }).collect();
-                                        let computed_memory = ComputedMemoryUsage {
-                                            total_memory: metrics.total_memory,
-                                            used_memory: metrics.used_memory,
-                                            memory_percent: metrics.memory_percent,
-                                            status: if metrics.memory_percent > 90.0 { "red".to_string() } else { "green".to_string() },
-                                        };
-                                        let disk_status = if computed_disks.iter().any(|d| d.status == "red") { "red" } else { "green" }.to_string();
-                                        let cpu_status = if metrics.cpu_usage > 90.0 { "red" } else { "green" }.to_string();
-                                        let memory_status = computed_memory.status.clone();
-                                        let overall_status = if disk_status == "red" || cpu_status == "red" || memory_status == "red" { "red" } else { "green" }.to_string();
-                                        
-                                        // Build a vector of red-status keys dynamically.
-                                        let status_keys = vec![
-                                            ("disk_status", disk_status.as_str()),
-                                            ("cpu_status", cpu_status.as_str()),
-                                            ("memory_status", memory_status.as_str()),
-                                            ("overall_status", overall_status.as_str()),
-                                        ];
-                                        let red_keys: Vec<&str> = status_keys.into_iter()
-                                            .filter_map(|(k, v)| if v == "red" { Some(k) } else { None })
-                                            .collect();
-                                        if *SLACK_ALERT_ENABLED && !red_keys.is_empty() {
-                                            let red_keys_str = red_keys.join(", ");
-                                            let alert_message = format!("Alert for {}: statuses [{}] are red at {}", fe.name, red_keys_str, crawl_time);
-                                            send_slack_alert(&alert_message).await;
-                                        }
-                                        
-                                        ServerUsage {
-                                            frontend: fe.clone(),
-                                            disk_usage: Some(computed_disks),
-                                            cpu_usage: Some(metrics.cpu_usage),
-                                            cpus: Some(computed_cpus),
-                                            memory_usage: Some(computed_memory),
-                                            disk_status,
-                                            cpu_status,
-                                            memory_status,
-                                            overall_status,
-                                            connectivity: "green".to_string(),
-                                            crawl_time: crawl_time.clone(),
-                                            status_history: None,
-                                        }
-                                    },
-                                    Err(err) => {
-                                        eprintln!("Failed to parse JSON for {}: {}", fe.name, err);
-                                        if *SLACK_ALERT_ENABLED {
-                                            let alert_message = format!("Alert for {}: Failed to parse JSON response at {}. Error: {}", fe.name, crawl_time, err);
-                                            send_slack_alert(&alert_message).await;
-                                        }
-                                        ServerUsage {
-                                            frontend: fe.clone(),
-                                            disk_usage: None,
-                                            cpu_usage: None,
-                                            cpus: None,
-                                            memory_usage: None,
-                                            disk_status: "red".to_string(),
-                                            cpu_status: "red".to_string(),
-                                            memory_status: "red".to_string(),
-                                            overall_status: "red".to_string(),
-                                            connectivity: "green".to_string(),
-                                            crawl_time: crawl_time.clone(),
-                                            status_history: None,
-                                        }
-                                    }
-                                }
-                            },
-                            Err(err) => {
-                                eprintln!("Error contacting frontend {}: {}", fe.name, err);
-                                if *SLACK_ALERT_ENABLED {
-                                    let alert_message = format!("Connectivity error for {}: Unable to reach at {}. Error: {}", fe.name, crawl_time, err);
-                                    send_slack_alert(&alert_message).await;
-                                }
-                                ServerUsage {
-                                    frontend: fe.clone(),
-                                    disk_usage: None,
-                                    cpu_usage: None,
-                                    cpus: None,
-                                    memory_usage: None,
-                                    disk_status: "red".to_string(),
-                                    cpu_status: "red".to_string(),
-                                    memory_status: "red".to_string(),
-                                    overall_status: "red".to_string(),
-                                    connectivity: "red".to_string(),
-                                    crawl_time: crawl_time.clone(),
-                                    status_history: None,
-                                }
-                            },
-                            _ => ServerUsage {
-                                frontend: fe.clone(),
-                                disk_usage: None,
-                                cpu_usage: None,
-                                cpus: None,
-                                memory_usage: None,
-                                disk_status: "red".to_string(),
-                                cpu_status: "red".to_string(),
-                                memory_status: "red".to_string(),
-                                overall_status: "red".to_string(),
-                                connectivity: "red".to_string(),
-                                crawl_time: crawl_time.clone(),
-                                status_history: None,
-                            }
-                        };
-                        usage
-                    } else if fe.frontend_type.to_lowercase() == "website" {
-                        let url = if fe.ip.starts_with("http://") || fe.ip.starts_with("https://") {
-                            fe.ip.clone()
-                        } else {
-                            format!("http://{}", fe.ip)
-                        };
-                        let website_status_code = match client.get(&url).send().await {
-                            Ok(resp) => resp.status().as_u16(),
-                            Err(err) => {
-                                eprintln!("Error contacting website {}: {}", fe.name, err);
-                                0
-                            }
-                        };
-                        let website_status = if website_status_code == 200 { "green".to_string() } else { "red".to_string() };
-                        let connectivity = if website_status_code != 0 { "green".to_string() } else { "red".to_string() };
-                        let status_record = StatusRecord {
-                            status_code: website_status_code,
-                            crawl_time: crawl_time.clone(),
-                        };
-                        {
-                            let mut history_map = WEBSITE_HISTORY.write().unwrap();
-                            let history_vec = history_map.entry(fe.name.clone()).or_insert(vec![]);
-                            history_vec.push(status_record.clone());
-                            if history_vec.len() > 3 {
-                                history_vec.remove(0);
-                            }
-                        }
-                        let history = WEBSITE_HISTORY.read().unwrap().get(&fe.name).cloned();
-                        if *SLACK_ALERT_ENABLED && website_status == "red" {
-                            let alert_message = format!("Alert for {}: website returned status {} at {}", fe.name, website_status_code, crawl_time);
-                            send_slack_alert(&alert_message).await;
-                        }
-                        ServerUsage {
-                            frontend: fe.clone(),
-                            disk_usage: None,
-                            cpu_usage: None,
-                            cpus: None,
-                            memory_usage: None,
-                            disk_status: website_status.clone(),
-                            cpu_status: website_status.clone(),
-                            memory_status: website_status.clone(),
-                            overall_status: website_status.clone(),
-                            connectivity,
-                            crawl_time: crawl_time.clone(),
-                            status_history: history,
-                        }
-                    } else {
-                        ServerUsage {
-                            frontend: fe.clone(),
-                            disk_usage: None,
-                            cpu_usage: None,
-                            cpus: None,
-                            memory_usage: None,
-                            disk_status: "red".to_string(),
-                            cpu_status: "red".to_string(),
-                            memory_status: "red".to_string(),
-                            overall_status: "red".to_string(),
-                            connectivity: "red".to_string(),
-                            crawl_time: crawl_time.clone(),
-                            status_history: None,
-                        }
-                    }
+        if REDIS.is_some() {
+            load_shared_frontends().await;
+            load_shared_alert_pause().await;
+            let held = POLLER.load(Ordering::Relaxed);
+            if held && !poller {
+                load_shared_alert_state().await;
+                last_polled.clear();
+            }
+            poller = held;
+        }
+        if REDIS.is_some() && !poller {
+            // Show what the polling backend sees, broadcasting only changes.
+            let usage = redis_get_raw(REDIS_USAGE_KEY).await;
+            if usage.is_some() && usage != shared_usage {
+                if let Some(usages) = usage.as_deref().and_then(|data| serde_json::from_str::<Vec<ServerUsage>>(data).ok()) {
+                    let usage_data: UsageData = usages.into_iter().map(|usage| (usage.frontend.name.clone(), usage)).collect();
+                    let usage_data = Arc::new(usage_data);
+                    *USAGE_DATA.write_or_recover() = Arc::clone(&usage_data);
+                    READY.store(true, Ordering::Relaxed);
+                    let _ = USAGE_UPDATES.send(usage_data);
                 }
+                shared_usage = usage;
+            }
+            tokio::select! {
+                _ = time::sleep(POLL_TICK) => {}
+                _ = shutdown.changed() => break,
+            }
+            continue;
+        }
+        let frontends = Arc::clone(&FRONTENDS.read_or_recover());
+        let now = Instant::now();
+        last_polled.retain(|name, _| frontends.contains_key(name));
+        let due: Vec<FrontendInfo> = frontends
+            .values()
+            .filter(|fe| {
+                last_polled.get(&fe.name).is_none_or(|last| now.duration_since(*last) >= fe.interval())
+            })
+            .cloned()
+            .collect();
+        for fe in &due {
+            last_polled.insert(fe.name.clone(), now);
+        }
+        let any_polled = !due.is_empty();
+        let websites_polled = due.iter().any(|fe| fe.frontend_type.to_lowercase() == "website");
+        let polls: Vec<_> = due
+            .into_iter()
+            .map(|fe| {
+                let (follow_redirects, insecure) = (fe.follow_redirects != Some(false), fe.insecure == Some(true));
+                let client = clients
+                    .entry((follow_redirects, insecure))
+                    .or_insert_with(|| poll_client(follow_redirects, insecure));
+                poll_frontend(client.clone(), fe)
             })
-            .buffered(100)
+            .collect();
+        let mut polled: HashMap<String, ServerUsage> = stream::iter(polls)
+            .buffered(*POLL_CONCURRENCY)
+            .map(|usage| (usage.frontend.name.clone(), usage))
             .collect()
             .await;
-        {
-            let mut usage_data = USAGE_DATA.write().unwrap();
-            *usage_data = new_usage_data;
+        let polled_names: Vec<String> = polled.keys().cloned().collect();
+        let snapshot = {
+            // Merge fresh results over the previous ones, keeping the configured order
+            // and dropping frontends that have since been deleted. The previous
+            // snapshot is only copied if a reader still holds it.
+            let mut usage_data = USAGE_DATA.write_or_recover();
+            let mut previous = Arc::try_unwrap(mem::take(&mut *usage_data)).unwrap_or_else(|arc| (*arc).clone());
+            let merged: UsageData = frontends
+                .keys()
+                .filter_map(|name| {
+                    let usage = polled.remove(name).or_else(|| previous.swap_remove(name))?;
+                    Some((name.clone(), usage))
+                })
+                .collect();
+            *usage_data = Arc::new(merged);
+            READY.store(true, Ordering::Relaxed);
+            if any_polled {
+                // Fails only when nobody is subscribed.
+                let _ = USAGE_UPDATES.send(Arc::clone(&usage_data));
+            }
+            Arc::clone(&usage_data)
+        };
+        if DB.is_some() && any_polled {
+            // Off the poll task, which shouldn't wait for the insert's fsync or
+            // for a long /api/history query holding the connection.
+            let snapshot = Arc::clone(&snapshot);
+            tokio::task::spawn_blocking(move || {
+                let Some(mut conn) = db() else {
+                    return;
+                };
+                if let Err(e) = store_history(&mut conn, polled_names.iter().filter_map(|name| snapshot.get(name))) {
+                    error!(error = %e, "Failed to store history in the database");
+                }
+            });
+        }
+        if any_polled && cache_saved.is_none_or(|at| at.elapsed() >= USAGE_CACHE_SAVE_INTERVAL) {
+            cache_saved = Some(Instant::now());
+            let snapshot = Arc::clone(&snapshot);
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = save_usage_cache(&snapshot) {
+                    error!(error = %e, "Failed to save usage cache");
+                }
+            });
+        }
+        if any_polled && REDIS.is_some() {
+            save_shared_state(&snapshot).await;
+        }
+        // Also catches history left behind when a website was deleted or
+        // changed type between polls.
+        let history_pruned = prune_website_history(&frontends);
+        if websites_polled || history_pruned {
+            let frontends = Arc::clone(&frontends);
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = save_website_history(&frontends) {
+                    error!(error = %e, "Failed to save website history");
+                }
+            });
+        }
+        tokio::select! {
+            _ = time::sleep(POLL_TICK) => {}
+            _ = shutdown.changed() => break,
+        }
+    }
+    // Released only once the keeper has stopped, so it can't renew it again.
+    if let Some(keeper) = keeper {
+        let _ = keeper.await;
+        if POLLER.load(Ordering::Relaxed) {
+            release_poller_lock().await;
+        }
+    }
+}
+
+// Whether a usage record is older than STALE_AFTER_INTERVALS of its frontend's
+// poll intervals plus the frontend's timeout. A crawl time that doesn't parse
+// counts as stale, and one whose limit doesn't fit in a Duration never does.
+fn is_stale(usage: &ServerUsage, now: DateTime<Utc>) -> bool {
+    if *STALE_AFTER_INTERVALS == 0 {
+        return false;
+    }
+    let Some(crawled) = NaiveDateTime::parse_from_str(&usage.crawl_time, CRAWL_TIME_FORMAT)
+        .ok()
+        .and_then(|crawled| crawled.and_local_timezone(*TZ_OFFSET).single())
+    else {
+        return true;
+    };
+    let age = (now - crawled.with_timezone(&Utc)).to_std().unwrap_or_default();
+    usage
+        .frontend
+        .interval()
+        .checked_mul(*STALE_AFTER_INTERVALS)
+        .and_then(|limit| limit.checked_add(usage.frontend.timeout()))
+        .is_some_and(|limit| age > limit)
+}
+
+// Keeps the stale flags up to date. This runs apart from the poll loop so that
+// a wedged loop still shows up on the dashboard.
+async fn watch_staleness(mut shutdown: watch::Receiver<bool>) {
+    loop {
+        tokio::select! {
+            _ = time::sleep(POLL_TICK) => {}
+            _ = shutdown.changed() => break,
+        }
+        let now = Utc::now();
+        let mut usage_data = USAGE_DATA.write_or_recover();
+        let changed: Vec<(String, bool)> = usage_data
+            .values()
+            .filter_map(|usage| {
+                let stale = is_stale(usage, now);
+                (stale != usage.stale).then(|| (usage.frontend.name.clone(), stale))
+            })
+            .collect();
+        if changed.is_empty() {
+            continue;
         }
-        time::sleep(Duration::from_secs(5)).await;
+        let data = Arc::make_mut(&mut usage_data);
+        for (name, stale) in changed {
+            if let Some(usage) = data.get_mut(&name) {
+                if stale {
+                    warn!(frontend = %name, crawl_time = %usage.crawl_time, "Frontend has not been polled recently");
+                }
+                usage.stale = stale;
+            }
+        }
+        // Fails only when nobody is subscribed.
+        let _ = USAGE_UPDATES.send(Arc::clone(&usage_data));
     }
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    tokio::spawn(async {
-        poll_frontends().await;
-    });
-    println!("Backend server running on http://127.0.0.1:8080");
-    HttpServer::new(|| {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+    load_config()?;
+    let tls_config = match (env::var("TLS_CERT"), env::var("TLS_KEY")) {
+        (Ok(cert), Ok(key)) => Some(load_tls_config(&cert, &key)?),
+        _ => None,
+    };
+    // Validate the webhook template now rather than on the first alert.
+    Lazy::force(&WEBHOOK_ALERTER);
+    Lazy::force(&DB);
+    if REDIS.is_some() {
+        // The first backend to start seeds the shared frontends from its file.
+        seed_shared_frontends().await;
+        load_shared_frontends().await;
+    }
+    let insecure: Vec<String> = FRONTENDS
+        .read_or_recover()
+        .values()
+        .filter(|fe| fe.insecure == Some(true))
+        .map(|fe| fe.name.clone())
+        .collect();
+    if !insecure.is_empty() {
+        warn!(frontends = ?insecure, "Certificate verification is disabled for these frontends");
+    }
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let poller = tokio::spawn(poll_frontends(shutdown_rx.clone()));
+    let staleness = tokio::spawn(watch_staleness(shutdown_rx));
+    let server = HttpServer::new(|| {
         App::new()
+            .wrap(from_fn(basic_auth))
+            .wrap(from_fn(rate_limit_mutations))
             .service(index)
+            .service(favicon)
+            .service(health)
+            .service(ready)
             .service(api_servers)
+            .service(api_server)
+            .service(api_server_history)
+            .service(api_server_series)
+            .service(api_history)
+            .service(api_schema)
+            .service(api_summary)
+            .service(api_frontends)
+            .service(api_incidents)
+            .service(api_incident_archives)
+            .service(api_stream)
+            .service(prometheus_metrics)
+            .service(export_csv)
             .service(add_frontend)
+            .service(import_frontends)
+            .service(edit_frontend)
             .service(delete_frontend)
-    })
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await
+            .service(mute_frontend)
+            .service(unmute_frontend)
+            .service(api_alerts)
+            .service(pause_alerts)
+            .service(resume_alerts)
+    });
+    let addr = bind_addr(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080);
+    let scheme = if tls_config.is_some() { "https" } else { "http" };
+    let server = match tls_config {
+        Some(config) => server.bind_rustls_0_23(addr, config)?,
+        None => server.bind(addr)?,
+    };
+    for addr in server.addrs() {
+        info!("Backend server running on {}://{}", scheme, addr);
+    }
+    // Actix stops accepting connections on SIGINT/SIGTERM and returns once
+    // in-flight requests have finished; then stop polling and flush state.
+    server.run().await?;
+    info!("Shutting down");
+    let _ = shutdown_tx.send(true);
+    if let Err(e) = poller.await {
+        error!(error = %e, "Poll loop exited abnormally");
+    }
+    let _ = staleness.await;
+    flush_state();
+    Ok(())
+}
+
+// Writes all persisted state to disk.
+fn flush_state() {
+    let frontends = FRONTENDS.read_or_recover();
+    if FRONTENDS_UNREADABLE.load(Ordering::Relaxed) {
+        warn!(file = FRONTENDS_FILE, "Leaving the frontends file that failed to load as it is");
+    } else if let Err(e) = save_frontends(&frontends) {
+        error!(error = %e, "Failed to save frontends");
+    }
+    if let Err(e) = save_website_history(&frontends) {
+        error!(error = %e, "Failed to save website history");
+    }
+    let usage_data = Arc::clone(&USAGE_DATA.read_or_recover());
+    if let Err(e) = save_usage_cache(&usage_data) {
+        error!(error = %e, "Failed to save usage cache");
+    }
+}
+
+// Settings from the optional CONFIG_PATH file. Each one stands in for the env
+// var of the same name, and the env var wins when both are set.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    poll_interval_secs: Option<u64>,
+    poll_concurrency: Option<usize>,
+    tz_offset_hours: Option<i32>,
+    thresholds: HashMap<String, ThresholdConfig>, // keyed by disk, inode, cpu, memory, swap, temp or gpu
+    alerts: AlertConfig,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThresholdConfig {
+    warn: Option<f64>,
+    crit: Option<f64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct AlertConfig {
+    cooldown_secs: Option<u64>,
+    dry_run: Option<bool>,
+    slack: Option<ChannelConfig>,
+    discord: Option<ChannelConfig>,
+    teams: Option<ChannelConfig>,
+    telegram: Option<TelegramConfig>,
+    email: Option<EmailConfig>,
+    webhook: Option<WebhookConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChannelConfig {
+    #[serde(default)]
+    enabled: bool,
+    webhook: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TelegramConfig {
+    #[serde(default)]
+    enabled: bool,
+    bot_token: Option<String>,
+    chat_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EmailConfig {
+    smtp_host: Option<String>,
+    smtp_port: Option<u16>,
+    smtp_user: Option<String>,
+    smtp_pass: Option<String>,
+    from: Option<String>,
+    to: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhookConfig {
+    url: Option<String>,
+    template: Option<String>,
+}
+
+impl Config {
+    // The settings as the env vars they stand in for.
+    fn env_vars(&self) -> Result<Vec<(String, String)>, String> {
+        let mut vars = vec![];
+        let mut set = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                vars.push((name.to_string(), value));
+            }
+        };
+        set("POLL_INTERVAL_SECS", self.poll_interval_secs.map(|v| v.to_string()));
+        set("POLL_CONCURRENCY", self.poll_concurrency.map(|v| v.to_string()));
+        set("TZ_OFFSET_HOURS", self.tz_offset_hours.map(|v| v.to_string()));
+        for (metric, thresholds) in &self.thresholds {
+            if !matches!(metric.as_str(), "disk" | "inode" | "cpu" | "memory" | "swap" | "temp" | "gpu") {
+                return Err(format!("Unknown threshold '{}', expected disk, inode, cpu, memory, swap, temp or gpu", metric));
+            }
+            let prefix = metric.to_uppercase();
+            set(&format!("{}_WARN", prefix), thresholds.warn.map(|v| v.to_string()));
+            // {P}_THRESHOLD in the environment still beats the file.
+            if env::var_os(format!("{}_THRESHOLD", prefix)).is_none() {
+                set(&format!("{}_CRIT", prefix), thresholds.crit.map(|v| v.to_string()));
+            }
+        }
+        let alerts = &self.alerts;
+        set("ALERT_COOLDOWN_SECS", alerts.cooldown_secs.map(|v| v.to_string()));
+        set("ALERT_DRY_RUN", alerts.dry_run.map(|v| v.to_string()));
+        for (prefix, channel) in [("SLACK", &alerts.slack), ("DISCORD", &alerts.discord), ("TEAMS", &alerts.teams)] {
+            if let Some(channel) = channel {
+                set(&format!("{}_ALERT", prefix), Some(channel.enabled.to_string()));
+                set(&format!("{}_WEBHOOK", prefix), channel.webhook.clone());
+            }
+        }
+        if let Some(telegram) = &alerts.telegram {
+            set("TELEGRAM_ALERT", Some(telegram.enabled.to_string()));
+            set("TELEGRAM_BOT_TOKEN", telegram.bot_token.clone());
+            set("TELEGRAM_CHAT_ID", telegram.chat_id.clone());
+        }
+        if let Some(email) = &alerts.email {
+            set("SMTP_HOST", email.smtp_host.clone());
+            set("SMTP_PORT", email.smtp_port.map(|v| v.to_string()));
+            set("SMTP_USER", email.smtp_user.clone());
+            set("SMTP_PASS", email.smtp_pass.clone());
+            set("ALERT_EMAIL_FROM", email.from.clone());
+            set("ALERT_EMAIL_TO", email.to.as_ref().map(|to| to.join(",")));
+        }
+        if let Some(webhook) = &alerts.webhook {
+            set("WEBHOOK_URL", webhook.url.clone());
+            set("WEBHOOK_TEMPLATE", webhook.template.clone());
+        }
+        Ok(vars)
+    }
+}
+
+// Reads CONFIG_PATH (default config.toml) and exports its settings as env
+// vars that are not already set, so the rest of the backend only reads the
+// environment. Must run before any setting is first read. A missing file is
+// fine; an invalid one is an error.
+fn load_config() -> std::io::Result<()> {
+    let path = env::var("CONFIG_PATH").unwrap_or_else(|_| "config.toml".to_string());
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(io::Error::new(e.kind(), format!("Failed to read {}: {}", path, e))),
+    };
+    let config_error = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid {}: {}", path, msg));
+    let config: Config = toml::from_str(&data).map_err(|e| config_error(e.to_string()))?;
+    for (name, value) in config.env_vars().map_err(config_error)? {
+        if env::var_os(&name).is_none() {
+            env::set_var(name, value);
+        }
+    }
+    info!(path = %path, "Loaded config file");
+    Ok(())
+}
+
+// Loads the PEM certificate chain and private key used to serve HTTPS.
+fn load_tls_config(cert_path: &str, key_path: &str) -> std::io::Result<rustls::ServerConfig> {
+    let tls_error = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+    let cert_file = File::open(cert_path)
+        .map_err(|e| tls_error(format!("Failed to open TLS_CERT {}: {}", cert_path, e)))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(cert_file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tls_error(format!("Failed to parse TLS_CERT {}: {}", cert_path, e)))?;
+    if certs.is_empty() {
+        return Err(tls_error(format!("No certificates found in TLS_CERT {}", cert_path)));
+    }
+    let key_file = File::open(key_path)
+        .map_err(|e| tls_error(format!("Failed to open TLS_KEY {}: {}", key_path, e)))?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(key_file))
+        .map_err(|e| tls_error(format!("Failed to parse TLS_KEY {}: {}", key_path, e)))?
+        .ok_or_else(|| tls_error(format!("No private key found in TLS_KEY {}", key_path)))?;
+    rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| tls_error(format!("Invalid TLS certificate or key: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str) -> FrontendInfo {
+        serde_json::from_str(&format!(r#"{{"name":"{}","ip":"10.0.0.1","type":"server"}}"#, name)).unwrap()
+    }
+
+    // Tests that go through the global state take turns, and run from an empty
+    // directory so they neither load nor overwrite the data files of a backend
+    // started from the source tree.
+    fn isolated_globals() -> MutexGuard<'static, ()> {
+        static GLOBALS: Mutex<()> = Mutex::new(());
+        let guard = GLOBALS.write_or_recover();
+        let dir = env::temp_dir().join(format!("rsm-globals-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        env::set_current_dir(&dir).unwrap();
+        guard
+    }
+
+    #[test]
+    fn status_serializes_as_lowercase_class_name() {
+        assert_eq!(serde_json::to_string(&Status::Green).unwrap(), r#""green""#);
+        assert_eq!(serde_json::to_string(&Status::Red).unwrap(), r#""red""#);
+        assert_eq!(serde_json::from_str::<Status>(r#""red""#).unwrap(), Status::Red);
+    }
+
+    #[test]
+    fn expected_status_parses_codes_and_ranges() {
+        let expected: ExpectedStatus = "200-299, 401".parse().unwrap();
+        assert!(expected.matches(204));
+        assert!(expected.matches(401));
+        assert!(!expected.matches(302));
+        assert!("299-200".parse::<ExpectedStatus>().is_err());
+        assert!("abc".parse::<ExpectedStatus>().is_err());
+
+        let single: ExpectedStatus = serde_json::from_str("302").unwrap();
+        assert_eq!(serde_json::to_string(&single).unwrap(), "302");
+        assert_eq!(serde_json::to_string(&expected).unwrap(), r#""200-299,401""#);
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("web01"), "web01");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn csv_import_reads_quoted_fields_and_rejects_bad_rows() {
+        assert_eq!(parse_csv_line(&csv_field("say \"hi\", bye")), vec!["say \"hi\", bye"]);
+        let body = "name,ip,type,interval_secs,tags\nweb01,10.0.0.1,server,30,\"prod, web\"\nweb02,10.0.0.2,server,soon,\n";
+        let entries = parse_import(body, true).unwrap();
+        let first = entries[0].as_ref().unwrap();
+        assert_eq!(first.interval_secs, Some(30));
+        assert_eq!(first.tags, vec!["prod", "web"]);
+        assert_eq!(entries[1].as_ref().unwrap_err().name, "web02");
+    }
+
+    #[test]
+    fn webhook_template_escapes_substituted_values() {
+        let rendered = render_webhook_template(
+            r#"{"text": "{frontend} is {status}: {message}", "at": "{time}"}"#,
+            "said \"hi\"\nbye",
+            "web",
+            Status::Red,
+            "2024-01-01 00:00:00",
+        );
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(value["text"], "web is red: said \"hi\"\nbye");
+        assert_eq!(value["at"], "2024-01-01 00:00:00");
+    }
+
+    #[test]
+    fn agent_url_fills_in_defaults() {
+        assert_eq!(agent_url("10.0.0.5").unwrap().as_str(), "http://10.0.0.5:8081/usage");
+        assert_eq!(agent_url("10.0.0.5:9000").unwrap().as_str(), "http://10.0.0.5:9000/usage");
+        assert_eq!(agent_url("https://agent.example.com").unwrap().as_str(), "https://agent.example.com/usage");
+        assert_eq!(agent_url("http://10.0.0.5:8081/metrics").unwrap().as_str(), "http://10.0.0.5:8081/metrics");
+        assert_eq!(service_url("10.0.0.5/nginx").unwrap().as_str(), "http://10.0.0.5:8081/service/nginx");
+        assert_eq!(
+            service_url("https://agent.example.com:9000/app@1.service").unwrap().as_str(),
+            "https://agent.example.com:9000/service/app@1.service"
+        );
+        assert!(service_url("http://10.0.0.5").is_err());
+    }
+
+    #[test]
+    fn validate_frontend_checks_address_against_type() {
+        assert!(validate_frontend("web", "example.com", "website").is_ok());
+        assert!(validate_frontend("web", "https://example.com/health", "website").is_ok());
+        assert!(validate_frontend("web", "htp://example.com", "website").is_err());
+        assert!(validate_frontend("agent", "http://10.0.0.5:8081/usage", "server").is_ok());
+        assert!(validate_frontend("agent", "10.0.0.5", "server").is_ok());
+        assert!(validate_frontend("router", "192.168.1.1", "ping").is_ok());
+        assert!(validate_frontend("router", "fe80::1", "ping").is_ok());
+        assert!(validate_frontend("router", "192.168.1.1:80", "ping").is_err());
+        assert!(validate_frontend("db", "10.0.0.5:5432", "port").is_ok());
+        assert!(validate_frontend("db", "10.0.0.5", "port").is_err());
+        assert!(validate_frontend("nginx", "10.0.0.5/nginx.service", "service").is_ok());
+        assert!(validate_frontend("nginx", "10.0.0.5", "service").is_err());
+        assert!(validate_frontend("web", "  ", "website").is_err());
+        assert!(validate_frontend("web", "example.com", "ftp").is_err());
+    }
+
+    #[test]
+    fn credentials_are_redacted_but_saved() {
+        let fe: FrontendInfo = serde_json::from_str(
+            r#"{"name":"site","ip":"https://example.com","type":"website","basic_auth_user":"admin","basic_auth_pass":"hunter2","headers":{"Authorization":"Bearer abc123"}}"#,
+        )
+        .unwrap();
+        assert!(serde_json::to_string(&fe).unwrap().contains("hunter2"));
+        let redacted = serde_json::to_string(&fe.redacted()).unwrap();
+        assert!(!redacted.contains("hunter2") && !redacted.contains("abc123"));
+        assert!(redacted.contains("admin"));
+        let debug = format!("{:?}", fe);
+        assert!(!debug.contains("hunter2") && !debug.contains("abc123"));
+    }
+
+    #[test]
+    fn new_frontend_names_must_differ_beyond_case_and_whitespace() {
+        let existing = server("web01");
+        let frontends: Frontends = [(existing.name.clone(), existing)].into_iter().collect();
+        let mut candidate: FrontendInfo =
+            serde_json::from_str(r#"{"name":"Web01 ","ip":"10.0.0.2","type":"server"}"#).unwrap();
+        let err = validate_new_frontend(&frontends, &candidate).unwrap_err();
+        assert!(err.contains("'Web01' conflicts with existing frontend 'web01'"), "{}", err);
+        candidate.name = "web02".to_string();
+        assert!(validate_new_frontend(&frontends, &candidate).is_ok());
+        candidate.timeout_secs = Some(u64::MAX);
+        assert!(validate_new_frontend(&frontends, &candidate).is_err());
+        assert_eq!(candidate.timeout(), Duration::from_secs(MAX_TIMEOUT_SECS));
+    }
+
+    #[test]
+    fn summary_counts_statuses_and_reports_the_worst() {
+        let fe = server("web01");
+        let green = ServerUsage::with_status(&fe, "", Status::Green);
+        let yellow = ServerUsage::with_status(&fe, "", Status::Yellow);
+        let red = ServerUsage::with_status(&fe, "", Status::Red);
+        assert_eq!(
+            summarize(&[&green, &red, &yellow, &green]),
+            Summary { total: 4, green: 2, yellow: 1, red: 1, stale: 0, worst_status: Status::Red }
+        );
+        assert_eq!(summarize(&[&green, &yellow]).worst_status, Status::Yellow);
+        assert_eq!(summarize(&[]).worst_status, Status::Green);
+    }
+
+    #[test]
+    fn leaving_red_for_degraded_counts_as_recovered() {
+        let fe = server("degraded01");
+        assert!(record_statuses(&ServerUsage::with_status(&fe, "", Status::Red)).newly_red.contains(&"overall_status"));
+        let changes = record_statuses(&ServerUsage::with_status(&fe, "", Status::Yellow));
+        assert!(changes.cleared.contains(&"overall_status"));
+        assert!(changes.recovered);
+        assert!(!record_statuses(&ServerUsage::with_status(&fe, "", Status::Green)).recovered);
+    }
+
+    #[test]
+    fn history_is_stored_and_queried_by_time_range() {
+        let mut conn = open_db(":memory:").unwrap();
+        let fe = server("web01");
+        for (crawl_time, cpu) in [("2024-05-01 10:00:00", 10.0), ("2024-05-01 10:01:00", 20.0), ("2024-05-01 10:02:00", 30.0)] {
+            let usage = ServerUsage { cpu_usage: Some(cpu), ..ServerUsage::with_status(&fe, crawl_time, Status::Green) };
+            store_history(&mut conn, [&usage]).unwrap();
+        }
+        let query = |from: Option<&str>, to: Option<&str>| HistoryQuery {
+            name: "web01".to_string(),
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+        };
+        let cpu = |history: History| history.metrics.iter().map(|m| m.cpu_usage.unwrap()).collect::<Vec<_>>();
+        assert_eq!(cpu(query_history(&conn, &query(None, None)).unwrap()), vec![10.0, 20.0, 30.0]);
+        assert_eq!(
+            cpu(query_history(&conn, &query(Some("2024-05-01 10:01:00"), Some("2024-05-01 10:01:59"))).unwrap()),
+            vec![20.0]
+        );
+        assert!(query_history(&conn, &query(None, None)).unwrap().status_history.is_empty());
+    }
+
+    #[test]
+    fn series_is_bucketed_the_same_in_sql_and_memory() {
+        let mut conn = open_db(":memory:").unwrap();
+        let fe = server("web01");
+        let samples = [("2024-05-01 10:00:10", 10.0), ("2024-05-01 10:00:50", 30.0), ("2024-05-01 10:01:00", 50.0)];
+        for (crawl_time, cpu) in samples {
+            let usage = ServerUsage { cpu_usage: Some(cpu as f32), ..ServerUsage::with_status(&fe, crawl_time, Status::Green) };
+            store_history(&mut conn, [&usage]).unwrap();
+        }
+        let expected = vec![
+            SeriesBucket { start: "2024-05-01 10:00:00".to_string(), min: 10.0, avg: 20.0, max: 30.0, count: 2 },
+            SeriesBucket { start: "2024-05-01 10:01:00".to_string(), min: 50.0, avg: 50.0, max: 50.0, count: 1 },
+        ];
+        assert_eq!(bucket_series(samples, 60), expected);
+        let query = SeriesQuery { metric: SeriesMetric::Cpu, bucket: Some(60), from: None, to: None };
+        assert_eq!(query_series(&conn, "web01", 60, &query).unwrap(), expected);
+        // No disks were stored, so there is nothing to aggregate.
+        let query = SeriesQuery { metric: SeriesMetric::Disk, ..query };
+        assert!(query_series(&conn, "web01", 60, &query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn usage_cached_by_an_older_version_still_loads() {
+        let fe = server("web01");
+        let mut cached = serde_json::to_value(ServerUsage::with_status(&fe, "2024-05-01 10:00:00", Status::Red)).unwrap();
+        for field in ["inode_status", "temp_status", "battery_status", "gpu_status", "uptime_percent", "flapping", "stale"] {
+            cached.as_object_mut().unwrap().remove(field);
+        }
+        let usage: ServerUsage = serde_json::from_value(cached).unwrap();
+        assert_eq!((usage.overall_status, usage.gpu_status), (Status::Red, Status::Green));
+    }
+
+    #[actix_web::test]
+    async fn frontends_deleted_by_another_backend_are_forgotten() {
+        let _globals = isolated_globals();
+        let (kept, deleted) = (server("shared-kept"), server("shared-deleted"));
+        let shared: BTreeMap<String, FrontendInfo> = [(kept.name.clone(), kept.clone())].into_iter().collect();
+        {
+            let mut frontends = FRONTENDS.write_or_recover();
+            let frontends = Arc::make_mut(&mut frontends);
+            frontends.insert(deleted.name.clone(), deleted.clone());
+            frontends.insert(kept.name.clone(), kept.clone());
+        }
+        for name in [&kept.name, &deleted.name] {
+            let statuses = HashMap::from([("cpu_status".to_string(), Status::Red)]);
+            LAST_STATUSES.write_or_recover().insert(name.clone(), statuses);
+            let sample = UptimeSample { at: Instant::now(), up: false };
+            UPTIME_SAMPLES.write_or_recover().entry(name.clone()).or_default().push_back(sample);
+            INCIDENTS.write_or_recover().push(Incident {
+                frontend: name.clone(),
+                metric: "cpu_status".to_string(),
+                started: "2024-05-01 10:00:00".to_string(),
+                ended: None,
+            });
+        }
+
+        apply_shared_frontends(shared);
+        assert!(FRONTENDS.read_or_recover().contains_key(&kept.name));
+        assert!(!FRONTENDS.read_or_recover().contains_key(&deleted.name));
+        assert!(LAST_STATUSES.read_or_recover().contains_key(&kept.name));
+        assert!(!LAST_STATUSES.read_or_recover().contains_key(&deleted.name));
+        assert!(!UPTIME_SAMPLES.read_or_recover().contains_key(&deleted.name));
+        let open = |name: &str| INCIDENTS.read_or_recover().iter().any(|i| i.frontend == name && i.ended.is_none());
+        assert!(open(&kept.name));
+        assert!(!open(&deleted.name));
+    }
+
+    // The globals guard only serializes tests, so holding it over the request is the point.
+    #[allow(clippy::await_holding_lock)]
+    #[actix_web::test]
+    async fn deleted_website_history_is_dropped() {
+        let _globals = isolated_globals();
+        let fe: FrontendInfo = serde_json::from_str(r#"{"name":"gone","ip":"example.com","type":"website"}"#).unwrap();
+        let record = StatusRecord { status_code: 200, crawl_time: "2024-05-01 10:00:00".to_string(), response_ms: Some(5) };
+        Arc::make_mut(&mut FRONTENDS.write_or_recover()).insert(fe.name.clone(), fe.clone());
+        WEBSITE_HISTORY.write_or_recover().entry(fe.name.clone()).or_default().push_back(record.clone());
+        assert!(!prune_website_history(&FRONTENDS.read_or_recover()));
+
+        let app = actix_web::test::init_service(App::new().service(delete_frontend)).await;
+        let mut req = actix_web::test::TestRequest::post().uri("/delete_frontend").set_form([("name", &fe.name)]);
+        if let Some(api_key) = &*API_KEY {
+            req = req.insert_header(("X-API-Key", api_key.as_str()));
+        }
+        let resp = actix_web::test::call_service(&app, req.to_request()).await;
+        assert!(resp.status().is_success());
+        assert!(!FRONTENDS.read_or_recover().contains_key(&fe.name));
+        assert!(!WEBSITE_HISTORY.read_or_recover().contains_key(&fe.name));
+
+        // Left behind by a deletion the poller didn't see.
+        WEBSITE_HISTORY.write_or_recover().entry(fe.name.clone()).or_default().push_back(record);
+        assert!(prune_website_history(&FRONTENDS.read_or_recover()));
+        assert!(!WEBSITE_HISTORY.read_or_recover().contains_key(&fe.name));
+    }
+
+    #[test]
+    fn website_body_defaults_to_json_post() {
+        let website = |extra: &str| -> FrontendInfo {
+            serde_json::from_str(&format!(r#"{{"name":"api","ip":"https://example.com/health","type":"website"{}}}"#, extra))
+                .unwrap()
+        };
+        let (method, headers) = website_request_parts(&website(""));
+        assert_eq!(method, Method::GET);
+        assert!(headers.is_empty());
+        let (method, headers) = website_request_parts(&website(r#","body":"{\"ping\":true}""#));
+        assert_eq!(method, Method::POST);
+        assert_eq!(headers[reqwest::header::CONTENT_TYPE], "application/json");
+        let (method, headers) = website_request_parts(&website(
+            r#","method":"put","body":"ping","headers":{"content-type":"text/plain"}"#,
+        ));
+        assert_eq!(method, Method::PUT);
+        assert_eq!(headers[reqwest::header::CONTENT_TYPE], "text/plain");
+    }
+
+    #[test]
+    fn red_metric_values_lists_only_red_readings() {
+        let fe = server("web01");
+        let disk = |mount_point: &str, used_percent, status| ComputedDiskUsage {
+            mount_point: mount_point.to_string(),
+            filesystem: "ext4".to_string(),
+            total: 100,
+            used: used_percent as u64,
+            total_human: String::new(),
+            used_human: String::new(),
+            used_percent,
+            status,
+            inodes_total: None,
+            inodes_used: None,
+            inodes_percent: None,
+            inode_status: None,
+            ignored: false,
+        };
+        let usage = ServerUsage {
+            cpu_usage: Some(96.24),
+            memory_usage: Some(ComputedMemoryUsage {
+                total_memory: 100,
+                used_memory: 50,
+                total_human: human_bytes(100),
+                used_human: human_bytes(50),
+                memory_percent: 50.0,
+                status: Status::Green,
+            }),
+            disk_usage: Some(vec![disk("/", 40.0, Status::Green), disk("/var", 97.0, Status::Red)]),
+            cpu_status: Status::Red,
+            battery: Some(BatteryInfo { percent: 85.0, on_ac: false }),
+            battery_status: Status::Red,
+            gpus: Some(vec![
+                GpuInfo { utilization_percent: 50.0, memory_used: 0, memory_total: 0 },
+                GpuInfo { utilization_percent: 99.0, memory_used: 0, memory_total: 0 },
+            ]),
+            gpu_status: Status::Red,
+            ..ServerUsage::with_status(&fe, "", Status::Green)
+        };
+        assert_eq!(
+            red_metric_values(&usage),
+            vec!["cpu=96.2%", "disk(/var)=97.0%", "battery=85.0% on battery", "gpu(1)=99.0%"]
+        );
+    }
+
+    #[test]
+    fn only_the_oldest_closed_incidents_are_archived() {
+        let incident = |started: &str, ended: Option<&str>| Incident {
+            frontend: "web01".to_string(),
+            metric: "cpu_status".to_string(),
+            started: started.to_string(),
+            ended: ended.map(String::from),
+        };
+        let incidents = vec![
+            incident("2024-05-01 10:00:00", None),
+            incident("2024-05-02 10:00:00", Some("2024-05-02 11:00:00")),
+            incident("2024-06-01 10:00:00", Some("2024-06-01 11:00:00")),
+            incident("2024-06-02 10:00:00", Some("2024-06-02 11:00:00")),
+        ];
+        let (kept, archived) = split_archivable(incidents.clone(), 2);
+        let started = |list: &[Incident]| list.iter().map(|i| i.started.clone()).collect::<Vec<_>>();
+        assert_eq!(started(&kept), ["2024-05-01 10:00:00", "2024-06-02 10:00:00"]);
+        assert_eq!(started(&archived), ["2024-05-02 10:00:00", "2024-06-01 10:00:00"]);
+        assert_eq!(incident_archive_file(&archived[0]), "incidents-2024-05.json");
+
+        // Open incidents stay even when that leaves the log over the cap.
+        let (kept, archived) = split_archivable(incidents, 0);
+        assert_eq!(started(&kept), ["2024-05-01 10:00:00"]);
+        assert_eq!(archived.len(), 3);
+    }
+
+    #[test]
+    fn usage_is_stale_after_missing_several_polls() {
+        let fe = FrontendInfo { interval_secs: Some(10), timeout_secs: Some(5), ..server("web01") };
+        let now = Utc::now();
+        let crawled_ago = |secs| {
+            let crawl_time = (now - chrono::Duration::seconds(secs)).with_timezone(&*TZ_OFFSET).format(CRAWL_TIME_FORMAT);
+            ServerUsage::with_status(&fe, &crawl_time.to_string(), Status::Green)
+        };
+        // Three 10 second intervals plus the 5 second timeout.
+        assert!(!is_stale(&crawled_ago(0), now));
+        assert!(!is_stale(&crawled_ago(34), now));
+        assert!(is_stale(&crawled_ago(36), now));
+        assert!(is_stale(&ServerUsage::with_status(&fe, "yesterday", Status::Green), now));
+        let usage = ServerUsage::with_status(&fe, "2000-01-01 00:00:00", Status::Green);
+        let never = ServerUsage { frontend: FrontendInfo { interval_secs: Some(u64::MAX), ..fe.clone() }, ..usage };
+        assert!(!is_stale(&never, now));
+    }
+
+    #[test]
+    fn token_bucket_allows_a_burst_then_refills() {
+        let mut buckets = HashMap::new();
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert!(take_token(&mut buckets, ip, 3, start));
+        }
+        assert!(!take_token(&mut buckets, ip, 3, start));
+        // Other clients have their own bucket.
+        assert!(take_token(&mut buckets, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 3, start));
+        // One token comes back every 20 seconds at 3 a minute.
+        assert!(!take_token(&mut buckets, ip, 3, start + Duration::from_secs(19)));
+        assert!(take_token(&mut buckets, ip, 3, start + Duration::from_secs(21)));
+    }
+
+    #[test]
+    fn human_bytes_uses_binary_units() {
+        assert_eq!(human_bytes(512), "512 B");
+        assert_eq!(human_bytes(1024), "1.0 KiB");
+        assert_eq!(human_bytes(1536 * 1024 * 1024), "1.5 GiB");
+        assert_eq!(human_bytes(2_000_000_000_000), "1.8 TiB");
+        assert_eq!(human_bytes(u64::MAX), "16.0 EiB");
+    }
+
+    #[test]
+    fn parse_version_orders_numerically() {
+        assert_eq!(parse_version("0.1.0"), Some([0, 1, 0]));
+        assert_eq!(parse_version("1.2"), Some([1, 2, 0]));
+        assert_eq!(parse_version("1.10.0-beta.1"), Some([1, 10, 0]));
+        assert!(parse_version("1.9.9") < parse_version("1.10.0"));
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    #[test]
+    fn config_file_maps_to_env_vars() {
+        let config: Config = toml::from_str(
+            r#"
+            poll_interval_secs = 10
+            [thresholds.disk]
+            warn = 80
+            crit = 95
+            [alerts.slack]
+            enabled = true
+            webhook = "https://hooks.example.com/x"
+            [alerts.email]
+            to = ["a@example.com", "b@example.com"]
+            "#,
+        )
+        .unwrap();
+        let vars: HashMap<String, String> = config.env_vars().unwrap().into_iter().collect();
+        assert_eq!(vars["POLL_INTERVAL_SECS"], "10");
+        assert_eq!(vars["DISK_WARN"], "80");
+        assert_eq!(vars["DISK_CRIT"], "95");
+        assert_eq!(vars["SLACK_ALERT"], "true");
+        assert_eq!(vars["SLACK_WEBHOOK"], "https://hooks.example.com/x");
+        assert_eq!(vars["ALERT_EMAIL_TO"], "a@example.com,b@example.com");
+        assert!(!vars.contains_key("DISCORD_ALERT"));
+
+        let config: Config = toml::from_str("[thresholds.gpu]\ncrit = 90").unwrap();
+        assert_eq!(config.env_vars().unwrap(), vec![("GPU_CRIT".to_string(), "90".to_string())]);
+        let config: Config = toml::from_str("[thresholds.fan]\ncrit = 90").unwrap();
+        assert!(config.env_vars().is_err());
+        assert!(toml::from_str::<Config>("poll_intervall_secs = 10").is_err());
+    }
+
+    #[test]
+    fn flapping_starts_above_threshold_and_stops_after_window() {
+        let start = Instant::now();
+        for i in 0..4 {
+            assert_eq!(record_flapping("flappy", true, start + Duration::from_secs(i)), (false, FlapChange::None));
+        }
+        let later = start + Duration::from_secs(10);
+        assert_eq!(record_flapping("flappy", true, later), (true, FlapChange::Started));
+        assert_eq!(record_flapping("flappy", false, later), (true, FlapChange::None));
+        let settled = later + Duration::from_secs(*FLAP_WINDOW_SECS);
+        assert_eq!(record_flapping("flappy", false, settled), (false, FlapChange::Stopped));
+    }
+
+    #[test]
+    fn interrupted_write_leaves_original_file_intact() {
+        let dir = env::temp_dir().join(format!("rsm-write-atomic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("frontends.json");
+        let original = r#"[{"name":"web01","ip":"10.0.0.1","type":"server"}]"#;
+        write_atomic(&path, original.as_bytes()).unwrap();
+
+        // Make the next save fail partway: a directory in place of the temp
+        // file can't be created.
+        let tmp_path = dir.join("frontends.json.tmp");
+        fs::create_dir(&tmp_path).unwrap();
+        assert!(write_atomic(&path, b"[]").is_err());
+
+        let data = fs::read_to_string(&path).unwrap();
+        assert_eq!(data, original);
+        let frontends: Vec<FrontendInfo> = serde_json::from_str(&data).unwrap();
+        assert_eq!(frontends[0].name, "web01");
+
+        // A later successful save replaces both a temp file left truncated by a
+        // crash and the original.
+        fs::remove_dir(&tmp_path).unwrap();
+        fs::write(&tmp_path, &original[..10]).unwrap();
+        write_atomic(&path, b"[]").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[]");
+        assert!(!tmp_path.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,13 +1,45 @@
-use actix_web::{get, App, HttpResponse, HttpServer, Responder};
-use serde::Serialize;
-use sysinfo::{CpuExt, DiskExt, System, SystemExt};
+use actix_web::{get, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use once_cell::sync::Lazy;
+use rust_server_monitor::{bind_addr, escape_label, LockExt};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::{ffi::CString, mem, os::unix::ffi::OsStrExt};
+#[cfg(any(feature = "systemd", feature = "gpu"))]
+use std::process::Command;
+#[cfg(feature = "gpu")]
+use std::{io::Read, process::Stdio};
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(not(target_os = "linux"))]
+use std::collections::HashSet;
+#[cfg(not(target_os = "linux"))]
+use sysinfo::Pid;
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr},
+    path::Path,
+    sync::RwLock,
+    thread,
+    time::{Duration, Instant},
+};
+use subtle::ConstantTimeEq;
+use sysinfo::{ComponentExt, CpuExt, DiskExt, NetworkExt, NetworksExt, PidExt, ProcessExt, System, SystemExt};
+use tracing::info;
+#[cfg(any(feature = "systemd", feature = "gpu"))]
+use tracing::warn;
+use tracing_subscriber::EnvFilter;
 
 #[derive(Serialize)]
 struct DiskUsage {
     mount_point: String,
+    filesystem: String,
     total: u64,
     used: u64,
     used_percent: f64,
+    // None where the filesystem has no fixed inode count or the OS has no inodes.
+    inodes_total: Option<u64>,
+    inodes_used: Option<u64>,
+    inodes_percent: Option<f64>,
 }
 
 #[derive(Serialize)]
@@ -17,6 +49,54 @@ struct CpuInfo {
     frequency: u64,
 }
 
+// Byte counters for one interface since boot. Loopback (`lo` on Linux) is
+// reported under its own name like any other interface.
+#[derive(Serialize)]
+struct NetworkInfo {
+    name: String,
+    received: u64,
+    transmitted: u64,
+}
+
+// Disk I/O of the whole host. The byte counts are cumulative since the agent
+// started; the rates cover the last refresh interval.
+#[derive(Serialize, Clone, Default)]
+struct DiskIo {
+    read_bytes: u64,
+    written_bytes: u64,
+    read_bytes_per_sec: f64,
+    write_bytes_per_sec: f64,
+}
+
+// A thermal sensor reading.
+#[derive(Serialize)]
+struct TempInfo {
+    label: String,
+    celsius: f32,
+}
+
+// 1, 5 and 15 minute load averages; zero where the OS has none (Windows).
+#[derive(Serialize)]
+struct LoadAverage {
+    one: f64,
+    five: f64,
+    fifteen: f64,
+}
+
+#[derive(Serialize, Clone)]
+struct BatteryInfo {
+    percent: f32,
+    on_ac: bool, // false while any battery is discharging
+}
+
+// An NVIDIA GPU as reported by nvidia-smi, with memory in bytes.
+#[derive(Serialize, Clone)]
+struct GpuInfo {
+    utilization_percent: f32,
+    memory_used: u64,
+    memory_total: u64,
+}
+
 #[derive(Serialize)]
 struct SystemMetrics {
     disk_usage: Vec<DiskUsage>,
@@ -25,16 +105,339 @@ struct SystemMetrics {
     total_memory: u64,
     used_memory: u64,
     memory_percent: f64,
+    total_swap: u64,
+    used_swap: u64,
+    swap_percent: f64,
+    networks: Vec<NetworkInfo>,
+    load_avg: LoadAverage,
+    disk_io: DiskIo,
+    temperatures: Vec<TempInfo>, // empty where the platform exposes no sensors
+    hostname: String,
+    os_name: String,
+    os_version: String,
+    kernel_version: String,
+    battery: Option<BatteryInfo>, // None on hosts without a battery
+    users: Vec<String>, // logged-in users, one entry per user however many sessions they have
+    gpus: Vec<GpuInfo>, // empty without the `gpu` feature, nvidia-smi or an NVIDIA GPU
+    version: &'static str, // agent version, so the backend can spot outdated agents
 }
 
-#[get("/usage")]
-async fn get_disk_usage() -> impl Responder {
-    let mut sys = System::new_all();
-    sys.refresh_all();
+#[derive(Serialize, Clone)]
+struct ProcessInfo {
+    name: String,
+    pid: u32,
+    cpu_usage: f32,
+    memory: u64,
+}
+
+#[derive(Serialize)]
+struct TopProcesses {
+    by_cpu: Vec<ProcessInfo>,
+    by_memory: Vec<ProcessInfo>,
+}
+
+#[cfg(feature = "systemd")]
+#[derive(Serialize)]
+struct ServiceState {
+    unit: String,
+    state: String, // as printed by `systemctl is-active`, e.g. "active" or "failed"
+}
+
+#[derive(Deserialize)]
+struct ProcessQuery {
+    limit: Option<usize>,
+}
+
+const DEFAULT_PROCESS_LIMIT: usize = 10;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// sysinfo computes CPU usage from the difference between two refreshes, so one
+// shared System is refreshed in the background and handlers read from it.
+static SYSTEM: Lazy<RwLock<System>> = Lazy::new(|| RwLock::new(System::new_all()));
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+// Zero until the first background refresh, so startup never reports the
+// host's lifetime I/O as a single interval.
+static DISK_IO: Lazy<RwLock<DiskIo>> = Lazy::new(|| RwLock::new(DiskIo::default()));
+// Refreshed with SYSTEM, since reading the utmp database is not thread-safe.
+static LOGGED_IN_USERS: Lazy<RwLock<Vec<String>>> = Lazy::new(|| RwLock::new(vec![]));
+// Refreshed alongside SYSTEM, so requests never read the batteries themselves.
+static BATTERY: Lazy<RwLock<Option<BatteryInfo>>> = Lazy::new(|| RwLock::new(None));
+// Refreshed on its own thread, so neither requests nor the other metrics ever
+// wait on nvidia-smi. Stays empty without the `gpu` feature.
+static GPUS: Lazy<RwLock<Vec<GpuInfo>>> = Lazy::new(|| RwLock::new(vec![]));
+// nvidia-smi is known to hang on a wedged driver, so it is killed after this.
+#[cfg(feature = "gpu")]
+const NVIDIA_SMI_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Filesystem types that are not real storage and are left out of disk usage.
+// Override with a comma-separated IGNORED_FILESYSTEMS.
+const DEFAULT_IGNORED_FILESYSTEMS: &[&str] = &[
+    "tmpfs", "devtmpfs", "overlay", "squashfs", "ramfs", "proc", "sysfs", "devpts",
+    "cgroup", "cgroup2", "mqueue", "debugfs", "tracefs", "securityfs", "pstore",
+    "bpf", "autofs", "fusectl", "configfs", "hugetlbfs", "nsfs",
+];
+static IGNORED_FILESYSTEMS: Lazy<Vec<String>> = Lazy::new(|| match env::var("IGNORED_FILESYSTEMS") {
+    Ok(val) => val
+        .split(',')
+        .map(|fs| fs.trim().to_lowercase())
+        .filter(|fs| !fs.is_empty())
+        .collect(),
+    Err(_) => DEFAULT_IGNORED_FILESYSTEMS.iter().map(|fs| fs.to_string()).collect(),
+});
+
+// Counts the bytes read and written between refreshes. Linux reads the
+// system-wide counters, which also cover processes that exited in between.
+// Elsewhere processes are summed, skipping each one's first refresh, whose
+// counts cover its whole life so far.
+#[derive(Default)]
+struct DiskIoCounter {
+    #[cfg(target_os = "linux")]
+    last: Option<(u64, u64)>,
+    #[cfg(not(target_os = "linux"))]
+    seen: HashSet<Pid>,
+}
+
+impl DiskIoCounter {
+    #[cfg(target_os = "linux")]
+    fn interval(&mut self, _sys: &System) -> (u64, u64) {
+        let current = fs::read_to_string("/proc/diskstats")
+            .ok()
+            .map(|stats| parse_diskstats(&stats, |name| Path::new("/sys/block").join(name).join("device").exists()));
+        let interval = match (self.last, current) {
+            (Some((last_read, last_written)), Some((read, written))) => {
+                (read.saturating_sub(last_read), written.saturating_sub(last_written))
+            }
+            _ => (0, 0),
+        };
+        self.last = current;
+        interval
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn interval(&mut self, sys: &System) -> (u64, u64) {
+        let mut seen = HashSet::new();
+        let mut interval = (0, 0);
+        for (pid, process) in sys.processes() {
+            // Each process reports the bytes it read and wrote since the last refresh.
+            if self.seen.contains(pid) {
+                let usage = process.disk_usage();
+                interval = (interval.0 + usage.read_bytes, interval.1 + usage.written_bytes);
+            }
+            seen.insert(*pid);
+        }
+        self.seen = seen;
+        interval
+    }
+}
+
+// Bytes read and written by whole disks, from /proc/diskstats. Only disks
+// backed by a device count, which leaves out partitions, loop, zram and
+// device-mapper devices, whose I/O their disk already counts. Sectors there
+// are always 512 bytes.
+#[cfg(target_os = "linux")]
+fn parse_diskstats(stats: &str, is_disk: impl Fn(&str) -> bool) -> (u64, u64) {
+    stats
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|fields| fields.len() >= 10 && is_disk(fields[2]))
+        .fold((0, 0), |(read, written), fields| {
+            let sectors = |i: usize| fields[i].parse::<u64>().unwrap_or(0);
+            (read + sectors(5) * 512, written + sectors(9) * 512)
+        })
+}
+
+fn refresh_system() {
+    let interval = REFRESH_INTERVAL.max(System::MINIMUM_CPU_UPDATE_INTERVAL);
+    let mut last_refresh = Instant::now();
+    let mut disk_io_counter = DiskIoCounter::default();
+    disk_io_counter.interval(&SYSTEM.read_or_recover());
+    loop {
+        thread::sleep(interval);
+        // Read before locking SYSTEM, which requests wait on.
+        let battery = battery_info();
+        let mut sys = SYSTEM.write_or_recover();
+        sys.refresh_disks_list();
+        sys.refresh_networks_list();
+        sys.refresh_all();
+        let elapsed = last_refresh.elapsed().as_secs_f64();
+        last_refresh = Instant::now();
+
+        let (read, written) = disk_io_counter.interval(&sys);
+        let mut disk_io = DISK_IO.write_or_recover();
+        disk_io.read_bytes += read;
+        disk_io.written_bytes += written;
+        disk_io.read_bytes_per_sec = read as f64 / elapsed;
+        disk_io.write_bytes_per_sec = written as f64 / elapsed;
+        *LOGGED_IN_USERS.write_or_recover() = logged_in_users();
+        *BATTERY.write_or_recover() = battery;
+    }
+}
+
+// Users with an interactive session, from the utmpx database. sysinfo's
+// users() lists every account on the host instead, logged in or not.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn logged_in_users() -> Vec<String> {
+    let mut users = vec![];
+    // SAFETY: only called from the refresh thread, so nothing else iterates
+    // the database concurrently, and each entry is copied before the next call.
+    unsafe {
+        libc::setutxent();
+        loop {
+            let entry = libc::getutxent();
+            if entry.is_null() {
+                break;
+            }
+            if (*entry).ut_type != libc::USER_PROCESS {
+                continue;
+            }
+            // ut_user is not NUL-terminated when the name fills it.
+            #[allow(clippy::unnecessary_cast)]
+            let name: Vec<u8> = (*entry).ut_user.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+            if !name.is_empty() {
+                users.push(String::from_utf8_lossy(&name).into_owned());
+            }
+        }
+        libc::endutxent();
+    }
+    users.sort();
+    users.dedup();
+    users
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn logged_in_users() -> Vec<String> {
+    vec![]
+}
+
+static AGENT_TOKEN: Lazy<Option<String>> = Lazy::new(|| {
+    env::var("AGENT_TOKEN").ok().filter(|token| !token.is_empty())
+});
+
+// True when no AGENT_TOKEN is set or the request carries it as a bearer token.
+fn is_authorized(req: &HttpRequest) -> bool {
+    let Some(token) = &*AGENT_TOKEN else {
+        return true;
+    };
+    let provided = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    provided.as_bytes().ct_eq(token.as_bytes()).into()
+}
+
+// Total and used inodes of the filesystem mounted at `path`. sysinfo does not
+// expose these, so they come from statvfs. None if that fails or the
+// filesystem allocates inodes dynamically (btrfs reports a total of zero).
+#[cfg(unix)]
+fn inode_usage(path: &Path) -> Option<(u64, u64)> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statvfs is plain old data, so all zeroes is a valid value.
+    let mut stat: libc::statvfs = unsafe { mem::zeroed() };
+    // SAFETY: path is NUL-terminated and stat is a valid place to write to.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // fsfilcnt_t is narrower than u64 on some platforms.
+    #[allow(clippy::unnecessary_cast)]
+    let (total, free) = (stat.f_files as u64, stat.f_ffree as u64);
+    if total == 0 {
+        return None;
+    }
+    Some((total, total.saturating_sub(free)))
+}
+
+#[cfg(not(unix))]
+fn inode_usage(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(feature = "gpu")]
+fn refresh_gpus() {
+    loop {
+        *GPUS.write_or_recover() = gpu_info();
+        thread::sleep(REFRESH_INTERVAL);
+    }
+}
+
+// Shells out to nvidia-smi, so it is only built with the `gpu` feature.
+#[cfg(feature = "gpu")]
+fn gpu_info() -> Vec<GpuInfo> {
+    let child = Command::new("nvidia-smi")
+        .args(["--query-gpu=utilization.gpu,memory.used,memory.total", "--format=csv,noheader,nounits"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return vec![];
+    };
+    let started = Instant::now();
+    // The output is a line per GPU, far too little to fill the pipe, so it
+    // can be read once nvidia-smi has exited.
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => {
+                let mut output = String::new();
+                if let Some(mut stdout) = child.stdout.take() {
+                    let _ = stdout.read_to_string(&mut output);
+                }
+                return parse_nvidia_smi(&output);
+            }
+            Ok(Some(_)) | Err(_) => return vec![],
+            Ok(None) if started.elapsed() >= NVIDIA_SMI_TIMEOUT => {
+                warn!(timeout = ?NVIDIA_SMI_TIMEOUT, "nvidia-smi did not finish in time, killing it");
+                let _ = child.kill();
+                let _ = child.wait();
+                return vec![];
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+// One line per GPU such as "45, 1024, 8192": utilization in percent and
+// memory in MiB. Lines with "[N/A]" fields are skipped.
+#[cfg(any(feature = "gpu", test))]
+fn parse_nvidia_smi(output: &str) -> Vec<GpuInfo> {
+    const MIB: u64 = 1024 * 1024;
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            Some(GpuInfo {
+                utilization_percent: fields.next()?.parse().ok()?,
+                memory_used: fields.next()?.parse::<u64>().ok()? * MIB,
+                memory_total: fields.next()?.parse::<u64>().ok()? * MIB,
+            })
+        })
+        .collect()
+}
+
+// Combined charge of all batteries, weighted by their capacity.
+fn battery_info() -> Option<BatteryInfo> {
+    let manager = battery::Manager::new().ok()?;
+    let batteries: Vec<battery::Battery> = manager.batteries().ok()?.filter_map(Result::ok).collect();
+    let full: f32 = batteries.iter().map(|b| b.energy_full().value).sum();
+    if full <= 0.0 {
+        return None;
+    }
+    let energy: f32 = batteries.iter().map(|b| b.energy().value).sum();
+    Some(BatteryInfo {
+        percent: (energy / full * 100.0).min(100.0),
+        on_ac: batteries.iter().all(|b| b.state() != battery::State::Discharging),
+    })
+}
 
+fn collect_metrics(sys: &System) -> SystemMetrics {
     let disk_info: Vec<DiskUsage> = sys.disks()
         .iter()
-        .map(|disk| {
+        .filter_map(|disk| {
+            let filesystem = String::from_utf8_lossy(disk.file_system()).to_string();
+            if IGNORED_FILESYSTEMS.contains(&filesystem.to_lowercase()) {
+                return None;
+            }
             let total = disk.total_space();
             let available = disk.available_space();
             let used = total.saturating_sub(available);
@@ -43,12 +446,17 @@ async fn get_disk_usage() -> impl Responder {
             } else {
                 0.0
             };
-            DiskUsage {
+            let inodes = inode_usage(disk.mount_point());
+            Some(DiskUsage {
                 mount_point: disk.mount_point().to_string_lossy().to_string(),
+                filesystem,
                 total,
                 used,
                 used_percent,
-            }
+                inodes_total: inodes.map(|(total, _)| total),
+                inodes_used: inodes.map(|(_, used)| used),
+                inodes_percent: inodes.map(|(total, used)| used as f64 / total as f64 * 100.0),
+            })
         })
         .collect();
 
@@ -70,24 +478,296 @@ async fn get_disk_usage() -> impl Responder {
         0.0
     };
 
-    let metrics = SystemMetrics {
+    let total_swap = sys.total_swap();
+    let used_swap = sys.used_swap();
+    let swap_percent = if total_swap > 0 {
+        (used_swap as f64 / total_swap as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    let mut networks: Vec<NetworkInfo> = sys.networks()
+        .iter()
+        .map(|(name, data)| NetworkInfo {
+            name: name.clone(),
+            received: data.total_received(),
+            transmitted: data.total_transmitted(),
+        })
+        .collect();
+    networks.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let load = sys.load_average();
+    let finite_or_zero = |value: f64| if value.is_finite() { value } else { 0.0 };
+    let load_avg = LoadAverage {
+        one: finite_or_zero(load.one),
+        five: finite_or_zero(load.five),
+        fifteen: finite_or_zero(load.fifteen),
+    };
+
+    let temperatures: Vec<TempInfo> = sys.components()
+        .iter()
+        .filter(|component| component.temperature().is_finite())
+        .map(|component| TempInfo {
+            label: component.label().to_string(),
+            celsius: component.temperature(),
+        })
+        .collect();
+
+    SystemMetrics {
         disk_usage: disk_info,
         cpu_usage,
         cpus,
         total_memory,
         used_memory,
         memory_percent,
-    };
+        total_swap,
+        used_swap,
+        swap_percent,
+        networks,
+        load_avg,
+        disk_io: DISK_IO.read_or_recover().clone(),
+        temperatures,
+        hostname: sys.host_name().unwrap_or_default(),
+        os_name: sys.name().unwrap_or_default(),
+        os_version: sys.os_version().unwrap_or_default(),
+        kernel_version: sys.kernel_version().unwrap_or_default(),
+        battery: BATTERY.read_or_recover().clone(),
+        users: LOGGED_IN_USERS.read_or_recover().clone(),
+        gpus: GPUS.read_or_recover().clone(),
+        version: VERSION,
+    }
+}
+
+#[get("/usage")]
+async fn get_disk_usage(req: HttpRequest) -> impl Responder {
+    if !is_authorized(&req) {
+        return HttpResponse::Unauthorized().body("Invalid or missing agent token");
+    }
+    let metrics = collect_metrics(&SYSTEM.read_or_recover());
     HttpResponse::Ok().json(metrics)
 }
 
+fn push_family(body: &mut String, name: &str, kind: &str, help: &str) {
+    body.push_str(&format!("# TYPE {} {}\n# HELP {} {}\n", name, kind, name, help));
+}
+
+// The /usage metrics in OpenMetrics format, for scraping the agent directly.
+// Byte counts are integers and floats use Display, so values are never written
+// in scientific notation.
+#[get("/metrics")]
+async fn prometheus_metrics(req: HttpRequest) -> impl Responder {
+    if !is_authorized(&req) {
+        return HttpResponse::Unauthorized().body("Invalid or missing agent token");
+    }
+    let metrics = collect_metrics(&SYSTEM.read_or_recover());
+    let mut body = String::new();
+
+    push_family(&mut body, "agent_cpu_usage", "gauge", "Global CPU usage percentage.");
+    body.push_str(&format!("agent_cpu_usage {}\n", metrics.cpu_usage));
+    push_family(&mut body, "agent_cpu_core_usage", "gauge", "CPU usage percentage per core.");
+    for cpu in &metrics.cpus {
+        body.push_str(&format!("agent_cpu_core_usage{{core=\"{}\"}} {}\n", escape_label(&cpu.name), cpu.cpu_usage));
+    }
+    push_family(&mut body, "agent_cpu_core_frequency_mhz", "gauge", "CPU frequency per core in MHz.");
+    for cpu in &metrics.cpus {
+        body.push_str(&format!("agent_cpu_core_frequency_mhz{{core=\"{}\"}} {}\n", escape_label(&cpu.name), cpu.frequency));
+    }
+
+    push_family(&mut body, "agent_memory_total_bytes", "gauge", "Total memory in bytes.");
+    body.push_str(&format!("agent_memory_total_bytes {}\n", metrics.total_memory));
+    push_family(&mut body, "agent_memory_used_bytes", "gauge", "Used memory in bytes.");
+    body.push_str(&format!("agent_memory_used_bytes {}\n", metrics.used_memory));
+    push_family(&mut body, "agent_memory_percent", "gauge", "Memory usage percentage.");
+    body.push_str(&format!("agent_memory_percent {}\n", metrics.memory_percent));
+    push_family(&mut body, "agent_swap_total_bytes", "gauge", "Total swap in bytes.");
+    body.push_str(&format!("agent_swap_total_bytes {}\n", metrics.total_swap));
+    push_family(&mut body, "agent_swap_used_bytes", "gauge", "Used swap in bytes.");
+    body.push_str(&format!("agent_swap_used_bytes {}\n", metrics.used_swap));
+
+    push_family(&mut body, "agent_disk_total_bytes", "gauge", "Disk size in bytes per mount point.");
+    for d in &metrics.disk_usage {
+        body.push_str(&format!("agent_disk_total_bytes{{mount=\"{}\"}} {}\n", escape_label(&d.mount_point), d.total));
+    }
+    push_family(&mut body, "agent_disk_used_bytes", "gauge", "Used disk space in bytes per mount point.");
+    for d in &metrics.disk_usage {
+        body.push_str(&format!("agent_disk_used_bytes{{mount=\"{}\"}} {}\n", escape_label(&d.mount_point), d.used));
+    }
+    push_family(&mut body, "agent_disk_used_percent", "gauge", "Disk usage percentage per mount point.");
+    for d in &metrics.disk_usage {
+        body.push_str(&format!("agent_disk_used_percent{{mount=\"{}\"}} {}\n", escape_label(&d.mount_point), d.used_percent));
+    }
+    push_family(&mut body, "agent_disk_inodes_used_percent", "gauge", "Inode usage percentage per mount point.");
+    for d in &metrics.disk_usage {
+        if let Some(percent) = d.inodes_percent {
+            body.push_str(&format!("agent_disk_inodes_used_percent{{mount=\"{}\"}} {}\n", escape_label(&d.mount_point), percent));
+        }
+    }
+
+    push_family(&mut body, "agent_disk_read_bytes", "counter", "Bytes read from disk.");
+    body.push_str(&format!("agent_disk_read_bytes_total {}\n", metrics.disk_io.read_bytes));
+    push_family(&mut body, "agent_disk_written_bytes", "counter", "Bytes written to disk.");
+    body.push_str(&format!("agent_disk_written_bytes_total {}\n", metrics.disk_io.written_bytes));
+
+    push_family(&mut body, "agent_temperature_celsius", "gauge", "Temperature per sensor in degrees Celsius.");
+    for t in &metrics.temperatures {
+        body.push_str(&format!("agent_temperature_celsius{{sensor=\"{}\"}} {}\n", escape_label(&t.label), t.celsius));
+    }
+
+    if let Some(battery) = &metrics.battery {
+        push_family(&mut body, "agent_battery_percent", "gauge", "Battery charge percentage.");
+        body.push_str(&format!("agent_battery_percent {}\n", battery.percent));
+        push_family(&mut body, "agent_battery_on_ac", "gauge", "Whether the host runs on AC power.");
+        body.push_str(&format!("agent_battery_on_ac {}\n", u8::from(battery.on_ac)));
+    }
+
+    push_family(&mut body, "agent_gpu_utilization_percent", "gauge", "GPU utilization percentage per GPU.");
+    for (index, gpu) in metrics.gpus.iter().enumerate() {
+        body.push_str(&format!("agent_gpu_utilization_percent{{gpu=\"{}\"}} {}\n", index, gpu.utilization_percent));
+    }
+    push_family(&mut body, "agent_gpu_memory_used_bytes", "gauge", "Used GPU memory in bytes per GPU.");
+    for (index, gpu) in metrics.gpus.iter().enumerate() {
+        body.push_str(&format!("agent_gpu_memory_used_bytes{{gpu=\"{}\"}} {}\n", index, gpu.memory_used));
+    }
+    push_family(&mut body, "agent_gpu_memory_total_bytes", "gauge", "Total GPU memory in bytes per GPU.");
+    for (index, gpu) in metrics.gpus.iter().enumerate() {
+        body.push_str(&format!("agent_gpu_memory_total_bytes{{gpu=\"{}\"}} {}\n", index, gpu.memory_total));
+    }
+
+    push_family(&mut body, "agent_logged_in_users", "gauge", "Number of users with an interactive session.");
+    body.push_str(&format!("agent_logged_in_users {}\n", metrics.users.len()));
+
+    push_family(&mut body, "agent_load_average", "gauge", "System load average.");
+    for (period, value) in [("1m", metrics.load_avg.one), ("5m", metrics.load_avg.five), ("15m", metrics.load_avg.fifteen)] {
+        body.push_str(&format!("agent_load_average{{period=\"{}\"}} {}\n", period, value));
+    }
+
+    push_family(&mut body, "agent_network_received_bytes", "counter", "Bytes received per interface.");
+    for n in &metrics.networks {
+        body.push_str(&format!("agent_network_received_bytes_total{{interface=\"{}\"}} {}\n", escape_label(&n.name), n.received));
+    }
+    push_family(&mut body, "agent_network_transmitted_bytes", "counter", "Bytes transmitted per interface.");
+    for n in &metrics.networks {
+        body.push_str(&format!("agent_network_transmitted_bytes_total{{interface=\"{}\"}} {}\n", escape_label(&n.name), n.transmitted));
+    }
+
+    body.push_str("# EOF\n");
+    HttpResponse::Ok()
+        .content_type("application/openmetrics-text; version=1.0.0; charset=utf-8")
+        .body(body)
+}
+
+// Whether a systemd unit is running, for "service" frontends. Only built with
+// the `systemd` feature, since it shells out to systemctl.
+#[cfg(feature = "systemd")]
+#[get("/service/{unit}")]
+async fn get_service_state(req: HttpRequest, unit: web::Path<String>) -> impl Responder {
+    if !is_authorized(&req) {
+        return HttpResponse::Unauthorized().body("Invalid or missing agent token");
+    }
+    let unit = unit.into_inner();
+    let valid = !unit.is_empty()
+        && !unit.starts_with('-')
+        && unit.chars().all(|c| c.is_ascii_alphanumeric() || "-_.@:\\".contains(c));
+    if !valid {
+        return HttpResponse::BadRequest().body("Invalid unit name");
+    }
+    let output = {
+        let unit = unit.clone();
+        web::block(move || Command::new("systemctl").args(["is-active", "--", &unit]).output()).await
+    };
+    match output {
+        // is-active exits non-zero for any state but "active" and still prints it.
+        Ok(Ok(output)) => {
+            let state = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            HttpResponse::Ok().json(ServiceState { unit, state })
+        }
+        Ok(Err(e)) => {
+            warn!(unit = %unit, error = %e, "Failed to run systemctl");
+            HttpResponse::InternalServerError().body(format!("Failed to run systemctl: {}", e))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+// The heaviest processes by CPU and by memory, `?limit=N` each (default 10).
+#[get("/processes")]
+async fn get_processes(req: HttpRequest, query: web::Query<ProcessQuery>) -> impl Responder {
+    if !is_authorized(&req) {
+        return HttpResponse::Unauthorized().body("Invalid or missing agent token");
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_PROCESS_LIMIT);
+    let sys = SYSTEM.read_or_recover();
+
+    let processes: Vec<ProcessInfo> = sys.processes()
+        .values()
+        .map(|process| ProcessInfo {
+            name: process.name().to_string(),
+            pid: process.pid().as_u32(),
+            cpu_usage: process.cpu_usage(),
+            memory: process.memory(),
+        })
+        .collect();
+
+    let mut by_cpu = processes.clone();
+    by_cpu.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    by_cpu.truncate(limit);
+    let mut by_memory = processes;
+    by_memory.sort_by_key(|p| std::cmp::Reverse(p.memory));
+    by_memory.truncate(limit);
+
+    HttpResponse::Ok().json(TopProcesses { by_cpu, by_memory })
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    println!("Frontend agent running on http://127.0.0.1:8081");
-    HttpServer::new(|| {
-        App::new().service(get_disk_usage)
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+    Lazy::force(&SYSTEM);
+    thread::spawn(refresh_system);
+    #[cfg(feature = "gpu")]
+    thread::spawn(refresh_gpus);
+    let server = HttpServer::new(|| {
+        let app = App::new()
+            .service(get_disk_usage)
+            .service(get_processes)
+            .service(prometheus_metrics);
+        #[cfg(feature = "systemd")]
+        let app = app.service(get_service_state);
+        app
     })
-    .bind(("127.0.0.1", 8081))?
-    .run()
-    .await
+    .bind(bind_addr(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8081))?;
+    for addr in server.addrs() {
+        info!("Frontend agent {} running on http://{}", VERSION, addr);
+    }
+    server.run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nvidia_smi_lines_with_missing_fields_are_skipped() {
+        let gpus = parse_nvidia_smi("45, 1024, 8192\n[N/A], 512, 4096\n3, [N/A], [N/A]\n 7 , 0, 16384\n");
+        let fields: Vec<(f32, u64, u64)> =
+            gpus.iter().map(|gpu| (gpu.utilization_percent, gpu.memory_used, gpu.memory_total)).collect();
+        const MIB: u64 = 1024 * 1024;
+        assert_eq!(fields, vec![(45.0, 1024 * MIB, 8192 * MIB), (7.0, 0, 16384 * MIB)]);
+        assert!(parse_nvidia_smi("").is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn diskstats_count_whole_disks_only() {
+        let stats = "\
+ 253       0 vda 900 10 2000 50 300 20 4000 60 0 100 110
+ 253       1 vda1 800 10 1500 40 250 20 3000 50 0 90 90
+   7       0 loop0 5 0 10 0 0 0 0 0 0 0 0
+ 259       0 nvme0n1 100 0 6 1 10 0 8 2 0 3 3
+ short line
+";
+        let totals = parse_diskstats(stats, |name| name == "vda" || name == "nvme0n1");
+        assert_eq!(totals, ((2000 + 6) * 512, (4000 + 8) * 512));
+    }
 }
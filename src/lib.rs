@@ -0,0 +1,123 @@
+// Code shared by the backend and the agent.
+
+use std::{
+    any::type_name,
+    env,
+    net::{IpAddr, SocketAddr},
+    sync::{LockResult, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+use tracing::{error, warn};
+
+// The listen address from BIND_ADDR and BIND_PORT, falling back to the
+// defaults for whichever is unset or invalid.
+pub fn bind_addr(default_ip: IpAddr, default_port: u16) -> SocketAddr {
+    let ip = match env::var("BIND_ADDR") {
+        Ok(val) => val.trim().parse::<IpAddr>().unwrap_or_else(|_| {
+            warn!(value = %val, "Invalid BIND_ADDR value, falling back to {}", default_ip);
+            default_ip
+        }),
+        Err(_) => default_ip,
+    };
+    let port = match env::var("BIND_PORT") {
+        Ok(val) => val.trim().parse::<u16>().unwrap_or_else(|_| {
+            warn!(value = %val, "Invalid BIND_PORT value, falling back to {}", default_port);
+            default_port
+        }),
+        Err(_) => default_port,
+    };
+    SocketAddr::new(ip, port)
+}
+
+// Escapes a Prometheus label value.
+pub fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// A panic while a lock is held poisons it, and unwrapping every later access
+// would take the whole process down with it. The guarded data may have been
+// left half-updated, but all of it is replaced or refreshed as the process
+// goes on, so recover the guard, clear the poison and log that it happened.
+// A Mutex has no shared access, so both of its methods lock it exclusively.
+pub trait LockExt<T> {
+    type ReadGuard<'a>
+    where
+        Self: 'a;
+    type WriteGuard<'a>
+    where
+        Self: 'a;
+
+    fn read_or_recover(&self) -> Self::ReadGuard<'_>;
+    fn write_or_recover(&self) -> Self::WriteGuard<'_>;
+}
+
+fn recover_poisoned<T, G>(result: LockResult<G>, clear_poison: impl FnOnce()) -> G {
+    result.unwrap_or_else(|poisoned| {
+        error!(lock = type_name::<T>(), "Recovered a lock poisoned by a panic");
+        clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+impl<T> LockExt<T> for RwLock<T> {
+    type ReadGuard<'a> = RwLockReadGuard<'a, T> where T: 'a;
+    type WriteGuard<'a> = RwLockWriteGuard<'a, T> where T: 'a;
+
+    fn read_or_recover(&self) -> RwLockReadGuard<'_, T> {
+        recover_poisoned::<T, _>(self.read(), || self.clear_poison())
+    }
+
+    fn write_or_recover(&self) -> RwLockWriteGuard<'_, T> {
+        recover_poisoned::<T, _>(self.write(), || self.clear_poison())
+    }
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    type ReadGuard<'a> = MutexGuard<'a, T> where T: 'a;
+    type WriteGuard<'a> = MutexGuard<'a, T> where T: 'a;
+
+    fn read_or_recover(&self) -> MutexGuard<'_, T> {
+        self.write_or_recover()
+    }
+
+    fn write_or_recover(&self) -> MutexGuard<'_, T> {
+        recover_poisoned::<T, _>(self.lock(), || self.clear_poison())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn escape_label_escapes_quotes_backslashes_and_newlines() {
+        assert_eq!(escape_label(r#"web "01"\a"#), r#"web \"01\"\\a"#);
+        assert_eq!(escape_label("a\nb"), r"a\nb");
+    }
+
+    #[test]
+    fn poisoned_lock_is_recovered_and_cleared() {
+        let lock = Arc::new(RwLock::new(vec![1]));
+        let poisoner = Arc::clone(&lock);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.write_or_recover();
+            panic!("poison the lock");
+        })
+        .join();
+        assert!(lock.is_poisoned());
+
+        lock.write_or_recover().push(2);
+        assert!(!lock.is_poisoned());
+        assert_eq!(*lock.read_or_recover(), vec![1, 2]);
+
+        let mutex = Arc::new(Mutex::new(1));
+        let poisoner = Arc::clone(&mutex);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.write_or_recover();
+            panic!("poison the mutex");
+        })
+        .join();
+        assert_eq!(*mutex.write_or_recover(), 1);
+        assert!(!mutex.is_poisoned());
+    }
+}